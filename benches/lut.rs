@@ -0,0 +1,76 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use ultimengine::{bitboard::BitBoard, counting, game::Game, lut};
+
+/// The sub-boards and meta-boards of a spread of random games
+fn boards() -> Vec<BitBoard> {
+	(0..80)
+		.map(Game::random)
+		.flat_map(|game| {
+			let mut boards = game.boards.to_vec();
+			boards.push(game.shrink());
+			boards
+		})
+		.collect()
+}
+
+fn lut(c: &mut Criterion) {
+	let boards = boards();
+	let mut group = c.benchmark_group("lut");
+
+	group.bench_function("one_aways/scan", |b| {
+		b.iter(|| {
+			black_box(&boards)
+				.iter()
+				.map(|brd| brd.one_aways_x() + brd.one_aways_o())
+				.sum::<i32>()
+		})
+	});
+	group.bench_function("one_aways/table", |b| {
+		b.iter(|| {
+			black_box(&boards)
+				.iter()
+				.map(|&brd| lut::one_aways_x(brd) + lut::one_aways_o(brd))
+				.sum::<i32>()
+		})
+	});
+
+	group.bench_function("possible_to_win/scan", |b| {
+		b.iter(|| {
+			black_box(&boards)
+				.iter()
+				.filter(|&&brd| counting::possible_to_win(brd))
+				.count()
+		})
+	});
+	group.bench_function("possible_to_win/table", |b| {
+		b.iter(|| {
+			black_box(&boards)
+				.iter()
+				.filter(|&&brd| lut::possible_to_win(brd))
+				.count()
+		})
+	});
+
+	group.bench_function("won/scan", |b| {
+		b.iter(|| {
+			black_box(&boards)
+				.iter()
+				.filter(|brd| brd.won_by_x() || brd.won_by_o())
+				.count()
+		})
+	});
+	group.bench_function("won/table", |b| {
+		b.iter(|| {
+			black_box(&boards)
+				.iter()
+				.filter(|&&brd| lut::won_by_x(brd) || lut::won_by_o(brd))
+				.count()
+		})
+	});
+
+	group.finish();
+}
+
+criterion_group!(benches, lut);
+criterion_main!(benches);
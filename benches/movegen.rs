@@ -0,0 +1,31 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use ultimengine::{
+	game::Game,
+	moves::{generate_moves, legal_moves},
+};
+
+fn movegen(c: &mut Criterion) {
+	let mut group = c.benchmark_group("movegen");
+
+	for mv_count in [0, 1, 20, 40] {
+		let game = Game::random(mv_count);
+
+		group.bench_with_input(
+			BenchmarkId::new("legal_moves", mv_count),
+			&game,
+			|b, game| b.iter(|| legal_moves(black_box(game))),
+		);
+
+		group.bench_with_input(
+			BenchmarkId::new("generate_moves", mv_count),
+			&game,
+			|b, game| b.iter(|| generate_moves(black_box(game))),
+		);
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, movegen);
+criterion_main!(benches);
@@ -1,5 +1,5 @@
 use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
-use ultimengine::{board::Slot, counting::alpha_beta, game::Game};
+use ultimengine::{counting::alpha_beta, game::Game};
 
 fn moves(c: &mut Criterion) {
 	let mut group = c.benchmark_group("moves");
@@ -12,9 +12,9 @@ fn moves(c: &mut Criterion) {
 				b.iter_batched(
 					|| Game::random(count - 1),
 					|mut game| {
-						let mv = alpha_beta(&game).1;
+						let mv = alpha_beta(&game).1.unwrap();
 
-						game.make_move(mv, Slot::X).unwrap()
+						game.make_move(mv).unwrap()
 					},
 					BatchSize::SmallInput,
 				);
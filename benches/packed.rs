@@ -0,0 +1,60 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use ultimengine::{board::State, game::Game, moves::generate_moves, packed::PackedGame};
+
+/// Plays the first legal move until the game ends
+fn playout_game(mut game: Game) -> State {
+	while game.state() == State::Undecided {
+		let Some(&mv) = generate_moves(&game).first() else {
+			break;
+		};
+
+		game.make_move(mv).unwrap();
+	}
+
+	game.state()
+}
+
+fn playout_packed(mut game: PackedGame) -> State {
+	while game.state() == State::Undecided {
+		let Some(mv) = game.legal_squares().next() else {
+			break;
+		};
+
+		game.make_move(mv).unwrap();
+	}
+
+	game.state()
+}
+
+fn packed(c: &mut Criterion) {
+	let mut group = c.benchmark_group("packed");
+
+	for mv_count in [0, 20] {
+		let game = Game::random(mv_count);
+		let pgame = PackedGame::from(&game);
+
+		group.bench_with_input(BenchmarkId::new("game", mv_count), &game, |b, game| {
+			b.iter(|| playout_game(black_box(game.clone())))
+		});
+
+		group.bench_with_input(BenchmarkId::new("packed", mv_count), &pgame, |b, game| {
+			b.iter(|| playout_packed(black_box(*game)))
+		});
+
+		group.bench_with_input(BenchmarkId::new("game_mask", mv_count), &game, |b, game| {
+			b.iter(|| black_box(game).legal_mask())
+		});
+
+		group.bench_with_input(
+			BenchmarkId::new("packed_mask", mv_count),
+			&pgame,
+			|b, game| b.iter(|| black_box(game).legal_mask()),
+		);
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, packed);
+criterion_main!(benches);
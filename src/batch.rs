@@ -0,0 +1,126 @@
+//! Scoring many positions at once, for labelling datasets and evaluating batches of leaves
+//!
+//! The mask scans behind `BoardTerms` run over every board of the batch together, several boards
+//! per SIMD instruction (see `simd::Backend::count_many`), and large batches are split across
+//! threads. The scores are exactly what `score_game_with` gives one position at a time.
+
+use std::thread;
+
+use crate::{
+	bitboard::BitBoard,
+	board::Slot,
+	eval::{BoardTerms, EvalCache, EvalParams},
+	game::Game,
+	generated::{ONE_AWAY_O, ONE_AWAY_X, WON_BY_O, WON_BY_X},
+	simd::Backend,
+};
+
+/// Batches with fewer games than this are scored on the calling thread
+pub const PARALLEL_THRESHOLD: usize = 1024;
+
+/// `score_game` of every game in `games`, each with the side that made its last move as `turn`
+pub fn evaluate_batch(games: &[Game]) -> Vec<i32> {
+	evaluate_batch_with(games, &EvalParams::DEFAULT)
+}
+
+/// `score_game_with` of every game in `games`, each with the side that made its last move as
+/// `turn`
+pub fn evaluate_batch_with(games: &[Game], params: &EvalParams) -> Vec<i32> {
+	let backend = Backend::detect();
+
+	if games.len() < PARALLEL_THRESHOLD {
+		return evaluate_chunk(games, params, backend);
+	}
+
+	let threads = thread::available_parallelism().map_or(1, |n| n.get());
+	let chunk = games.len().div_ceil(threads);
+
+	thread::scope(|s| {
+		let handles: Vec<_> = games
+			.chunks(chunk)
+			.map(|chunk| s.spawn(move || evaluate_chunk(chunk, params, backend)))
+			.collect();
+
+		handles
+			.into_iter()
+			.flat_map(|h| h.join().unwrap())
+			.collect()
+	})
+}
+
+fn evaluate_chunk(games: &[Game], params: &EvalParams, backend: Backend) -> Vec<i32> {
+	// The nine boards of every game followed by its meta-board
+	let boards: Vec<u32> = games
+		.iter()
+		.flat_map(|g| g.boards.iter().map(|b| b.0).chain([g.shrink().0]))
+		.collect();
+
+	let count = |masks: &[u32]| {
+		let mut out = vec![0; boards.len()];
+		backend.count_many(&boards, masks, &mut out);
+
+		out
+	};
+
+	let one_aways_x = count(&ONE_AWAY_X);
+	let one_aways_o = count(&ONE_AWAY_O);
+	let won_x = count(&WON_BY_X);
+	let won_o = count(&WON_BY_O);
+
+	let terms = |idx: usize| {
+		let board = BitBoard(boards[idx]);
+		let won_by = if won_x[idx] != 0 {
+			Slot::X
+		} else if won_o[idx] != 0 {
+			Slot::O
+		} else {
+			Slot::Empty
+		};
+
+		BoardTerms {
+			state: board.state(),
+			won_by,
+			corners_x: board.corners(Slot::X),
+			corners_o: board.corners(Slot::O),
+			one_aways_x: one_aways_x[idx] as i32,
+			one_aways_o: one_aways_o[idx] as i32,
+		}
+	};
+
+	games
+		.iter()
+		.enumerate()
+		.map(|(gdx, game)| {
+			let cache = EvalCache::from_terms(
+				std::array::from_fn(|bdx| terms(gdx * 10 + bdx)),
+				terms(gdx * 10 + 9),
+			);
+
+			cache.score_game(game.active, game.to_move().flip().slot(), params)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::{PARALLEL_THRESHOLD, evaluate_batch};
+	use crate::{counting::score_game, game::Game};
+
+	#[test]
+	fn matches_score_game() {
+		let games: Vec<Game> = (0..PARALLEL_THRESHOLD + 70)
+			.map(|n| Game::random((n % 70) as u8))
+			.collect();
+
+		// Small enough to stay on this thread, then big enough to be split. Both sides are to
+		// move somewhere in each
+		for len in [37, games.len()] {
+			let expected: Vec<i32> = games[..len]
+				.iter()
+				.map(|g| score_game(g, g.to_move().flip().slot()))
+				.collect();
+
+			assert_eq!(evaluate_batch(&games[..len]), expected);
+		}
+	}
+}
@@ -0,0 +1,21 @@
+//! Regenerates the opening book, which `openingbook.rs` includes at compile time
+//!
+//! Usage: `book [output file]`, `openingbook.serialized` by default. The book is the search's
+//! replies to the first moves, so it has to be regenerated (and the engine rebuilt) whenever the
+//! evaluation or search changes.
+
+use ultimengine::openingbook::OpeningBook;
+
+fn main() {
+	let path = std::env::args()
+		.nth(1)
+		.unwrap_or_else(|| "openingbook.serialized".to_string());
+
+	// `Debug` writes the book as the Rust expression `include!` reads back
+	let book = format!("{:?}", OpeningBook::generate());
+
+	if std::fs::write(&path, book).is_err() {
+		eprintln!("could not write {path}");
+		std::process::exit(1);
+	}
+}
@@ -0,0 +1,43 @@
+//! Trains the neural network evaluator from self-play
+//!
+//! Usage: `nn <generations> <games per generation> <output weight file> [seed]`
+//! The weights are saved after every generation.
+
+use ultimengine::nn::train_from_scratch;
+
+fn main() {
+	let args: Vec<String> = std::env::args().collect();
+
+	if args.len() < 4 {
+		eprintln!(
+			"usage: {} <generations> <games per generation> <output weights> [seed]",
+			args[0]
+		);
+		std::process::exit(1);
+	}
+
+	let fail = |e: &str| -> ! {
+		eprintln!("{e}");
+		std::process::exit(1);
+	};
+
+	let generations = args[1]
+		.parse()
+		.unwrap_or_else(|_| fail("generations must be a number"));
+	let games = args[2]
+		.parse()
+		.unwrap_or_else(|_| fail("games must be a number"));
+	let seed = match args.get(4) {
+		Some(seed) => seed
+			.parse()
+			.unwrap_or_else(|_| fail("seed must be a number")),
+		None => 42,
+	};
+
+	train_from_scratch(generations, games, 4, seed, |gen_idx, net, loss| {
+		println!("generation {gen_idx}: loss {loss:.5}");
+
+		net.save(&args[3])
+			.unwrap_or_else(|_| fail("could not write weights"));
+	});
+}
@@ -0,0 +1,51 @@
+//! Learns evaluation weights through TD(λ) self-play
+//!
+//! Usage: `td <games> <output weight file> [seed] [starting weight file]`
+//! Checkpoints are written next to the output every 100 games.
+
+use ultimengine::{
+	eval::EvalParams,
+	td::{TdConfig, train},
+};
+
+fn main() {
+	let args: Vec<String> = std::env::args().collect();
+
+	if args.len() < 3 {
+		eprintln!(
+			"usage: {} <games> <output weights> [seed] [starting weights]",
+			args[0]
+		);
+		std::process::exit(1);
+	}
+
+	let fail = |e: &str| -> ! {
+		eprintln!("{e}");
+		std::process::exit(1);
+	};
+
+	let mut cfg = TdConfig {
+		games: args[1]
+			.parse()
+			.unwrap_or_else(|_| fail("games must be a number")),
+		checkpoint_prefix: Some(args[2].trim_end_matches(".toml").into()),
+		..TdConfig::default()
+	};
+
+	if let Some(seed) = args.get(3) {
+		cfg.seed = seed
+			.parse()
+			.unwrap_or_else(|_| fail("seed must be a number"));
+	}
+
+	let start = match args.get(4) {
+		Some(path) => EvalParams::load(path).unwrap_or_else(|e| fail(e)),
+		None => EvalParams::DEFAULT,
+	};
+
+	let learned =
+		train(start, &cfg).unwrap_or_else(|e| fail(&format!("could not write checkpoint: {e}")));
+
+	std::fs::write(&args[2], learned.to_string())
+		.unwrap_or_else(|_| fail("could not write weights"));
+}
@@ -0,0 +1,42 @@
+//! Tunes the evaluation weights against a dataset of finished games
+//!
+//! Usage: `tune <dataset> <output weight file> [starting weight file]`
+
+use ultimengine::{
+	eval::EvalParams,
+	tuner::{error, find_k, load_dataset, tune},
+};
+
+fn main() {
+	let args: Vec<String> = std::env::args().collect();
+
+	if args.len() < 3 {
+		eprintln!(
+			"usage: {} <dataset> <output weights> [starting weights]",
+			args[0]
+		);
+		std::process::exit(1);
+	}
+
+	let fail = |e: &str| -> ! {
+		eprintln!("{e}");
+		std::process::exit(1);
+	};
+
+	let src = std::fs::read_to_string(&args[1]).unwrap_or_else(|_| fail("could not read dataset"));
+	let samples = load_dataset(&src).unwrap_or_else(|e| fail(e));
+
+	let start = match args.get(3) {
+		Some(path) => EvalParams::load(path).unwrap_or_else(|e| fail(e)),
+		None => EvalParams::DEFAULT,
+	};
+
+	let k = find_k(&samples, &start);
+	println!("{} positions, k = {k:.4}", samples.len());
+	println!("starting error: {:.6}", error(&samples, &start, k));
+
+	let tuned = tune(&samples, start, k, 1000);
+	println!("tuned error:    {:.6}", error(&samples, &tuned, k));
+
+	std::fs::write(&args[2], tuned.to_string()).unwrap_or_else(|_| fail("could not write weights"));
+}
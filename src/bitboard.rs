@@ -7,7 +7,8 @@ use crate::{
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
 /// u32 holding 3 x 9 variants + 5 bits for state
-/// First 9 bits are X, then O, then Empty, then 2 bits for state, 3 unused:
+/// First 9 bits are X, then O, then Empty, then 2 bits for state, 3 unused (except in
+/// `Game::boards[0]`, which keeps the game state and side to move there):
 /// 00011   111111111   111111111   111111111
 /// state   empty bit   o brd bit   x brd bit
 pub struct BitBoard(pub u32);
@@ -28,7 +29,11 @@ pub mod consts {
 	pub const ST_MASK: u32 = 0b00011 << ST_OFFS;
 
 	pub const UN_OFFS: u32 = 29;
-	pub const UN_MASK: u32 = MASK << UN_OFFS;
+	pub const UN_MASK: u32 = 0b11 << UN_OFFS;
+
+	/// Only used in `Game::boards[0]`, set when O is to move
+	pub const TURN_OFFS: u32 = 31;
+	pub const TURN_MASK: u32 = 1 << TURN_OFFS;
 }
 
 impl BitBoard {
@@ -129,22 +134,19 @@ impl BitBoard {
 		}
 	}
 
+	/// Counts every one of the 24 masks, one at a time (the NEON version below takes 12 per step)
 	#[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
 	const fn one_aways<const FOR_X: bool>(self) -> i32 {
 		let mut n = 0;
 		let mut idx = 0;
 		let arr = const { if FOR_X { ONE_AWAY_X } else { ONE_AWAY_O } };
 
-		loop {
-			if idx >= 24 {
-				break;
-			}
-
+		while idx < arr.len() {
 			let mask = arr[idx];
 
 			n += (mask == (mask & self.0)) as i32;
 
-			idx += 8;
+			idx += 1;
 		}
 
 		n
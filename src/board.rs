@@ -1,6 +1,13 @@
 use std::fmt::Display;
 
+use crate::moves::MoveError;
+
 #[derive(Clone, Copy, Debug, PartialEq, Hash, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "lowercase")
+)]
 #[repr(u8)]
 pub enum Slot {
 	Empty,
@@ -28,6 +35,58 @@ impl Slot {
 	}
 }
 
+/// A side that can make moves, unlike `Slot` it can't be empty or disabled
+#[derive(Clone, Copy, Debug, PartialEq, Hash, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "lowercase")
+)]
+pub enum Player {
+	X,
+	O,
+}
+
+impl Player {
+	pub const fn flip(self) -> Self {
+		match self {
+			Player::X => Player::O,
+			Player::O => Player::X,
+		}
+	}
+
+	pub const fn slot(self) -> Slot {
+		match self {
+			Player::X => Slot::X,
+			Player::O => Slot::O,
+		}
+	}
+}
+
+impl From<Player> for Slot {
+	fn from(player: Player) -> Slot {
+		player.slot()
+	}
+}
+
+impl TryFrom<Slot> for Player {
+	type Error = MoveError;
+
+	fn try_from(slot: Slot) -> Result<Player, Self::Error> {
+		match slot {
+			Slot::X => Ok(Player::X),
+			Slot::O => Ok(Player::O),
+			_ => Err(MoveError::NotASide),
+		}
+	}
+}
+
+impl Display for Player {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.slot().fmt(f)
+	}
+}
+
 impl Display for Slot {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "{}", self.to_chr())
@@ -35,6 +94,11 @@ impl Display for Slot {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "lowercase")
+)]
 pub enum State {
 	Won,
 	Lost,
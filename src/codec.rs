@@ -0,0 +1,359 @@
+//! A compact binary format for positions, for storing training data
+//!
+//! A position is `ENCODED_LEN` bytes: the 81 cells in base 3 (`.` 0, `x` 1, `o` 2), five cells to a
+//! byte starting from square 0. The last byte holds cell 80 together with the active board (0-8,
+//! 9 for any) and the side to move, as `cell + 3 * active + 30 * side` with `x` 0 and `o` 1. The
+//! states of the boards and the game aren't stored since they follow from the cells.
+//!
+//! A file of positions is `MAGIC` followed by records of a position and its label as a
+//! little-endian `f32`, with nothing at the end so it can be appended to.
+
+use std::{
+	fmt::Display,
+	io::{self, Read, Write},
+};
+
+use crate::{
+	bitboard::BitBoard,
+	board::{Player, Slot},
+	game::{Game, decided_state},
+	notation::NotationError,
+};
+
+pub const ENCODED_LEN: usize = 17;
+pub const MAGIC: [u8; 8] = *b"UTTTPOS1";
+/// Bytes per record in a file
+pub const RECORD_LEN: usize = ENCODED_LEN + 4;
+
+/// Why bytes aren't a valid position or file of positions
+#[derive(Debug)]
+pub enum CodecError {
+	Io(io::Error),
+	/// The file doesn't start with `MAGIC`
+	Magic,
+	/// The byte at `index` is `value`, which is out of range for the cells it packs
+	Encoding {
+		index: u8,
+		value: u8,
+	},
+	/// The cells are fine but the position can't come up in a game
+	Position(NotationError),
+}
+
+impl CodecError {
+	pub const fn message(&self) -> &'static str {
+		match self {
+			CodecError::Io(_) => "couldn't read or write positions",
+			CodecError::Magic => "not a file of positions",
+			CodecError::Encoding { .. } => "byte out of range for a position",
+			CodecError::Position(_) => "not a valid position",
+		}
+	}
+}
+
+impl Display for CodecError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			CodecError::Io(err) => write!(f, "{}: {err}", self.message()),
+			CodecError::Encoding { index, value } => {
+				write!(f, "{} (byte {index} is {value})", self.message())
+			}
+			CodecError::Position(err) => write!(f, "{}: {err}", self.message()),
+			CodecError::Magic => f.write_str(self.message()),
+		}
+	}
+}
+
+impl std::error::Error for CodecError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			CodecError::Io(err) => Some(err),
+			CodecError::Position(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for CodecError {
+	fn from(err: io::Error) -> Self {
+		CodecError::Io(err)
+	}
+}
+
+impl Game {
+	pub fn encode(&self) -> [u8; ENCODED_LEN] {
+		let mut out = [0; ENCODED_LEN];
+
+		let cells = self.boards.iter().flat_map(|brd| brd.to_arr());
+		for (sq, slot) in cells.enumerate() {
+			let digit = match slot {
+				Slot::X => 1,
+				Slot::O => 2,
+				_ => 0,
+			};
+
+			out[sq / 5] += digit * 3u8.pow(sq as u32 % 5);
+		}
+
+		let side = match self.to_move() {
+			Player::X => 0,
+			Player::O => 1,
+		};
+		out[ENCODED_LEN - 1] += 3 * self.active + 30 * side;
+
+		out
+	}
+
+	/// Reads a position written by `encode`, which has to pass `validate` like in `from_boards`
+	pub fn decode(bytes: &[u8; ENCODED_LEN]) -> Result<Game, CodecError> {
+		let mut slots = [[Slot::Empty; 9]; 9];
+
+		for (idx, &byte) in bytes.iter().enumerate() {
+			let (limit, count) = if idx == ENCODED_LEN - 1 {
+				(60, 1)
+			} else {
+				(243, 5)
+			};
+
+			if byte >= limit {
+				return Err(CodecError::Encoding {
+					index: idx as u8,
+					value: byte,
+				});
+			}
+
+			let mut rest = byte;
+			for sq in idx * 5..idx * 5 + count {
+				slots[sq / 9][sq % 9] = match rest % 3 {
+					1 => Slot::X,
+					2 => Slot::O,
+					_ => Slot::Empty,
+				};
+				rest /= 3;
+			}
+		}
+
+		let extra = bytes[ENCODED_LEN - 1] / 3;
+		let (active, side) = (extra % 10, if extra >= 10 { Player::O } else { Player::X });
+
+		let boards = slots.map(|cells| {
+			let mut brd = BitBoard::new_with(cells);
+			brd.set_state(decided_state(brd));
+			brd
+		});
+
+		Game::from_boards(boards, side, active).map_err(CodecError::Position)
+	}
+}
+
+/// Writes labelled positions to a file, see the module docs
+pub struct PositionWriter<W: Write> {
+	inner: W,
+}
+
+impl<W: Write> PositionWriter<W> {
+	/// Starts a new file by writing `MAGIC`
+	pub fn new(mut inner: W) -> io::Result<Self> {
+		inner.write_all(&MAGIC)?;
+
+		Ok(PositionWriter { inner })
+	}
+
+	/// Carries on a file that already has its header, like one opened for appending
+	pub fn append(inner: W) -> Self {
+		PositionWriter { inner }
+	}
+
+	pub fn write(&mut self, game: &Game, label: f32) -> io::Result<()> {
+		let mut record = [0; RECORD_LEN];
+		record[..ENCODED_LEN].copy_from_slice(&game.encode());
+		record[ENCODED_LEN..].copy_from_slice(&label.to_le_bytes());
+
+		self.inner.write_all(&record)
+	}
+
+	/// Flushes and hands back the writer
+	pub fn finish(mut self) -> io::Result<W> {
+		self.inner.flush()?;
+
+		Ok(self.inner)
+	}
+}
+
+/// Reads labelled positions from a file one at a time, see the module docs
+pub struct PositionReader<R: Read> {
+	inner: R,
+}
+
+impl<R: Read> PositionReader<R> {
+	/// Checks the file starts with `MAGIC`
+	pub fn new(mut inner: R) -> Result<Self, CodecError> {
+		let mut magic = [0; MAGIC.len()];
+
+		match inner.read_exact(&mut magic) {
+			Ok(()) if magic == MAGIC => Ok(PositionReader { inner }),
+			Ok(()) => Err(CodecError::Magic),
+			Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Err(CodecError::Magic),
+			Err(err) => Err(err.into()),
+		}
+	}
+
+	/// The next record, `None` at the end of the file and an error if it ends partway through one
+	pub fn read(&mut self) -> Option<Result<(Game, f32), CodecError>> {
+		let mut record = [0; RECORD_LEN];
+		let mut filled = 0;
+
+		while filled < RECORD_LEN {
+			match self.inner.read(&mut record[filled..]) {
+				Ok(0) if filled == 0 => return None,
+				Ok(0) => return Some(Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())),
+				Ok(n) => filled += n,
+				Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+				Err(err) => return Some(Err(err.into())),
+			}
+		}
+
+		let (pos, label) = record.split_at(ENCODED_LEN);
+		let label = f32::from_le_bytes(label.try_into().unwrap());
+
+		Some(Game::decode(pos.try_into().unwrap()).map(|game| (game, label)))
+	}
+}
+
+impl<R: Read> Iterator for PositionReader<R> {
+	type Item = Result<(Game, f32), CodecError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.read()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use rand::{Rng, SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+
+	use super::{CodecError, ENCODED_LEN, MAGIC, PositionReader, PositionWriter};
+	use crate::{board::State, game::Game, moves::generate_moves};
+
+	/// Every position of a random game from `seed`
+	fn playout(seed: u64) -> Vec<Game> {
+		let mut rng = SmallRng::seed_from_u64(seed);
+		let mut games = vec![Game::new()];
+
+		loop {
+			let game = games.last().unwrap();
+			let lgms = generate_moves(game);
+
+			if lgms.is_empty() || game.state() != State::Undecided {
+				break games;
+			}
+
+			games.push(game.sim_move(*lgms.choose(&mut rng).unwrap()).unwrap());
+		}
+	}
+
+	#[test]
+	fn round_trips() {
+		// Any board active and X to move
+		let mut empty = [0; ENCODED_LEN];
+		empty[ENCODED_LEN - 1] = 3 * 9;
+		assert_eq!(Game::new().encode(), empty);
+
+		for seed in 0..300 {
+			for game in playout(seed) {
+				assert_eq!(Game::decode(&game.encode()).unwrap(), game);
+			}
+		}
+	}
+
+	#[test]
+	fn decode_is_exact() {
+		// Whatever decodes has to encode back to the same bytes
+		let mut rng = SmallRng::seed_from_u64(7);
+
+		for _ in 0..20_000 {
+			let mut bytes = [0; ENCODED_LEN];
+			for (idx, byte) in bytes.iter_mut().enumerate() {
+				// Mostly empty cells so that some of them are valid positions
+				*byte = if rng.random_bool(0.7) {
+					0
+				} else {
+					rng.random_range(0..if idx == ENCODED_LEN - 1 { 64 } else { 255 })
+				};
+			}
+
+			if let Ok(game) = Game::decode(&bytes) {
+				assert_eq!(game.encode(), bytes);
+			}
+		}
+
+		let mut bytes = Game::new().encode();
+		bytes[3] = 243;
+		assert!(matches!(
+			Game::decode(&bytes),
+			Err(CodecError::Encoding {
+				index: 3,
+				value: 243
+			})
+		));
+
+		bytes = Game::new().encode();
+		bytes[ENCODED_LEN - 1] = 60;
+		assert!(matches!(
+			Game::decode(&bytes),
+			Err(CodecError::Encoding {
+				index: 16,
+				value: 60
+			})
+		));
+		assert_eq!(
+			Game::decode(&bytes).unwrap_err().to_string(),
+			"byte out of range for a position (byte 16 is 60)"
+		);
+
+		// xxxoo in board a with a as the active board
+		bytes = Game::new().encode();
+		bytes[0] = 1 + 3 + 9 + 2 * 27 + 2 * 81;
+		bytes[ENCODED_LEN - 1] = 30 + 3 * 9;
+		assert!(Game::decode(&bytes).is_ok());
+		bytes[ENCODED_LEN - 1] = 0;
+		assert!(matches!(Game::decode(&bytes), Err(CodecError::Position(_))));
+	}
+
+	#[test]
+	fn streams() {
+		let games = playout(1);
+		let mut writer = PositionWriter::new(vec![]).unwrap();
+
+		for (ply, game) in games.iter().enumerate() {
+			writer.write(game, ply as f32 / 2.0).unwrap();
+		}
+
+		let file = writer.finish().unwrap();
+		assert_eq!(file.len(), MAGIC.len() + games.len() * (ENCODED_LEN + 4));
+
+		let read: Vec<_> = PositionReader::new(&file[..])
+			.unwrap()
+			.collect::<Result<_, _>>()
+			.unwrap();
+
+		assert_eq!(read.len(), games.len());
+		for (ply, (game, label)) in read.into_iter().enumerate() {
+			assert_eq!(game, games[ply]);
+			assert_eq!(label, ply as f32 / 2.0);
+		}
+
+		let mut cut = PositionReader::new(&file[..file.len() - 1]).unwrap();
+		assert!(cut.by_ref().take(games.len() - 1).all(|rec| rec.is_ok()));
+		assert!(matches!(cut.next(), Some(Err(CodecError::Io(_)))));
+
+		assert!(matches!(
+			PositionReader::new(&file[1..]),
+			Err(CodecError::Magic)
+		));
+		assert!(matches!(
+			PositionReader::new(&b"UTT"[..]),
+			Err(CodecError::Magic)
+		));
+	}
+}
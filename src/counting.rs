@@ -1,88 +1,174 @@
-use std::cmp::{max, min};
+use std::cmp::{Reverse, max, min};
 
 use crate::{
 	bitboard::{
 		BitBoard,
 		consts::{O_MASK, X_MASK},
 	},
-	board::{Slot, State},
-	game::Game,
-	generated::POSSIBLE_TO_WIN,
-	moves::{Move, legal_moves},
+	board::{Player, Slot, State},
+	eval::{BoardTerms, EvalCache, EvalParams, Evaluator},
+	game::{Game, Undo},
+	generated::{POSSIBLE_TO_WIN, WON_BY_X},
+	moves::{Move, generate_moves},
 	openingbook::OBOOK,
 };
 
-pub fn engine_mv(game: &Game) -> Move {
+/// The engine's move for the side to move, `None` if there isn't a legal one
+pub fn engine_mv(game: &Game) -> Option<Move> {
+	engine_mv_with(game, &EvalParams::DEFAULT)
+}
+
+pub fn engine_mv_with(game: &Game, evaluator: &(impl Evaluator + ?Sized)) -> Option<Move> {
 	for (gm, rf) in OBOOK.0.iter() {
 		if gm == game {
-			return *rf;
+			return Some(*rf);
 		}
 	}
 
-	alpha_beta(game).1
+	alpha_beta_with(game, evaluator).1
 }
 
-pub fn alpha_beta(game: &Game) -> (i32, Move) {
-	// 15 is the highest that fits in the u4 of storage for each field
-	let mut mv = Move::new(15, 15);
+/// A search's score (from X's point of view) and move, as returned by `alpha_beta`, in a form that
+/// can be serialized. `mv` is `None` if there was no legal move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult {
+	pub score: i32,
+	pub mv: Option<Move>,
+}
 
+impl From<(i32, Option<Move>)> for SearchResult {
+	fn from((score, mv): (i32, Option<Move>)) -> Self {
+		SearchResult { score, mv }
+	}
+}
+
+pub fn alpha_beta(game: &Game) -> (i32, Option<Move>) {
+	alpha_beta_with(game, &EvalParams::DEFAULT)
+}
+
+pub fn alpha_beta_with(game: &Game, evaluator: &(impl Evaluator + ?Sized)) -> (i32, Option<Move>) {
 	let num_moves_made: u32 = game
 		.boards
 		.map(|b| (b.0 & X_MASK).count_ones() + (b.0 & O_MASK).count_ones())
 		.into_iter()
 		.sum();
 
-	let scr = if num_moves_made >= 14 {
-		_alpha_beta::<true, 13>(game, &mut mv, 0, i32::MIN, i32::MAX)
+	if num_moves_made >= 14 {
+		search_with::<13>(game, evaluator)
+	} else {
+		search_with::<11>(game, evaluator)
+	}
+}
+
+/// Fixed depth search which picks a move for the side to move, `None` if there isn't a legal one
+pub fn search_with<const MAX_DEPTH: u8>(
+	game: &Game,
+	evaluator: &(impl Evaluator + ?Sized),
+) -> (i32, Option<Move>) {
+	let mut mv = None;
+	let cache = evaluator.new_cache(game);
+	let mut game = game.clone();
+
+	let scr = if game.to_move() == Player::X {
+		_alpha_beta::<true, MAX_DEPTH, _>(
+			&mut game,
+			&cache,
+			evaluator,
+			&mut mv,
+			0,
+			i32::MIN,
+			i32::MAX,
+		)
 	} else {
-		_alpha_beta::<true, 11>(game, &mut mv, 0, i32::MIN, i32::MAX)
+		_alpha_beta::<false, MAX_DEPTH, _>(
+			&mut game,
+			&cache,
+			evaluator,
+			&mut mv,
+			0,
+			i32::MIN,
+			i32::MAX,
+		)
 	};
 
 	(scr, mv)
 }
 
-fn _alpha_beta<const IS_MAX: bool, const MAX_DEPTH: u8>(
-	game: &Game,
-	choice: &mut Move,
+/// Plays `mv` in place and returns the evaluator's cache for the new position, along with what's
+/// needed to take the move back
+#[inline]
+fn make_child<E: Evaluator + ?Sized>(
+	game: &mut Game,
+	cache: &E::Cache,
+	evaluator: &E,
+	mv: Move,
+) -> (E::Cache, Undo) {
+	let undo = game.make_move_unchecked(mv);
+	let mut cache = cache.clone();
+
+	evaluator.update_cache(&mut cache, game, &undo);
+
+	(cache, undo)
+}
+
+fn _alpha_beta<const IS_MAX: bool, const MAX_DEPTH: u8, E: Evaluator + ?Sized>(
+	game: &mut Game,
+	cache: &E::Cache,
+	evaluator: &E,
+	choice: &mut Option<Move>,
 	depth: u8,
 	mut alp: i32,
 	mut bet: i32,
 ) -> i32 {
 	if depth >= MAX_DEPTH || game.state() != State::Undecided {
-		return score_game(game, if IS_MAX { Slot::O } else { Slot::X });
+		return evaluator.score_cached(cache, game, if IS_MAX { Slot::O } else { Slot::X });
 	}
 
-	let mut lgs = legal_moves(game);
+	let side = if IS_MAX { Slot::X } else { Slot::O };
+	debug_assert_eq!(game.to_move().slot(), side);
+	let mut lgs = generate_moves(game);
 
-	if IS_MAX {
-		let mut value = i32::MIN;
+	// Scoring the games to sort them is costly
+	// but alpha-beta pruning benefits so much from
+	// a sorted list that it's worth it (see sortdepthanalysis.txt).
+	if depth <= 8 {
+		// By `Move::square`
+		let mut scores = [0; 81];
 
-		// Scoring the games to sort them is costly
-		// but alpha-beta pruning benefits so much from
-		// a sorted list that it's worth it (see sortdepthanalysis.txt)
-		if depth <= 8 {
-			lgs.sort_unstable_by(|a, b| {
-				let asim = unsafe { game.sim_move(*a, Slot::X).unwrap_unchecked() };
-				let bsim = unsafe { game.sim_move(*b, Slot::X).unwrap_unchecked() };
+		for &mv in lgs.iter() {
+			let (cache, undo) = make_child(game, cache, evaluator, mv);
+			scores[mv.square() as usize] = evaluator.score_cached(&cache, game, side);
 
-				score_game(&bsim, Slot::X).cmp(&score_game(&asim, Slot::X))
-			});
+			game.unmake_move(undo);
 		}
 
+		lgs.sort_unstable_by_key(|mv| Reverse(scores[mv.square() as usize]));
+	} else if depth + 2 < MAX_DEPTH {
+		lgs.sort_unstable_by_key(|&mv| Reverse(evaluator.order_hint(cache, mv, side)));
+	}
+
+	if IS_MAX {
+		let mut value = i32::MIN;
+
 		for legal in lgs {
-			let sim = unsafe { game.sim_move(legal, Slot::X).unwrap_unchecked() };
+			let (child_cache, undo) = make_child(game, cache, evaluator, legal);
 			// TODO: use table
 
-			let eval = _alpha_beta::<false, MAX_DEPTH>(
-				&sim,
+			let eval = _alpha_beta::<false, MAX_DEPTH, E>(
+				game,
+				&child_cache,
+				evaluator,
 				choice,
-				depth + 1 + (sim.active == 9) as u8,
+				depth + 1 + (game.active == 9) as u8,
 				alp,
 				bet,
 			);
 
+			game.unmake_move(undo);
+
 			if eval > value && depth == 0 {
-				*choice = legal;
+				*choice = Some(legal);
 			}
 			value = max(value, eval);
 
@@ -96,27 +182,23 @@ fn _alpha_beta<const IS_MAX: bool, const MAX_DEPTH: u8>(
 	} else {
 		let mut value = i32::MAX;
 
-		if depth <= 8 {
-			lgs.sort_unstable_by(|a, b| {
-				let asim = unsafe { game.sim_move(*a, Slot::O).unwrap_unchecked() };
-				let bsim = unsafe { game.sim_move(*b, Slot::O).unwrap_unchecked() };
-
-				score_game(&bsim, Slot::O).cmp(&score_game(&asim, Slot::O))
-			});
-		}
-
 		for legal in lgs {
-			let sim = unsafe { game.sim_move(legal, Slot::O).unwrap_unchecked() };
-			let eval = _alpha_beta::<true, MAX_DEPTH>(
-				&sim,
+			let (child_cache, undo) = make_child(game, cache, evaluator, legal);
+
+			let eval = _alpha_beta::<true, MAX_DEPTH, E>(
+				game,
+				&child_cache,
+				evaluator,
 				choice,
-				depth + 1 + 2 * (sim.active == 9) as u8,
+				depth + 1 + 2 * (game.active == 9) as u8,
 				alp,
 				bet,
 			);
 
+			game.unmake_move(undo);
+
 			if eval < value && depth == 0 {
-				*choice = legal;
+				*choice = Some(legal);
 			}
 			value = min(value, eval);
 
@@ -131,52 +213,59 @@ fn _alpha_beta<const IS_MAX: bool, const MAX_DEPTH: u8>(
 }
 
 pub fn score_game(game: &Game, turn: Slot) -> i32 {
-	let mut scr = score(game.shrink(), turn) * 100;
+	score_game_with(game, turn, &EvalParams::DEFAULT)
+}
+
+pub fn score_game_with(game: &Game, turn: Slot, params: &EvalParams) -> i32 {
+	EvalCache::new(game).score_game(game.active, turn, params)
+}
+
+/// For every board, a bitmask of the meta lines (indices into `WON_BY_X`) running through it
+pub(crate) const LINES_THROUGH: [u8; 9] = {
+	let mut through = [0; 9];
+	let mut line = 0;
+
+	while line < 8 {
+		let mut idx = 0;
 
-	scr += game.boards.map(|b| score(b, turn) / 4).iter().sum::<i32>();
+		while idx < 9 {
+			if WON_BY_X[line] & (1 << idx) != 0 {
+				through[idx] |= 1 << line;
+			}
 
-	for st in game.boards.map(BitBoard::state) {
-		if st == State::Won {
-			scr += 100;
-		} else if st == State::Lost {
-			scr -= 100;
+			idx += 1;
 		}
-	}
 
-	if game.active == 9 && turn == Slot::X {
-		scr -= scr / 3;
-	} else if game.active == 9 && turn == Slot::O {
-		scr += scr / 3;
+		line += 1;
 	}
 
-	scr
+	through
+};
+
+/// See `EvalCache::live_lines`
+pub fn live_lines(game: &Game) -> (u8, u8) {
+	EvalCache::new(game).live_lines()
+}
+
+/// See `EvalCache::line_score`
+pub fn line_score(game: &Game, params: &EvalParams) -> i32 {
+	EvalCache::new(game).line_score(params)
+}
+
+/// See `EvalCache::send_score`
+pub fn send_score(game: &Game, turn: Slot, params: &EvalParams) -> i32 {
+	EvalCache::new(game).send_score(game.active, turn, params)
 }
 
 // Takes a `Board` and returns a "score" for how good it is for `X`
 #[inline]
 pub fn score(board: BitBoard, turn: Slot) -> i32 {
-	if board.won_by_x() {
-		return 10_000;
-	} else if board.won_by_o() {
-		return -10_000;
-	}
-
-	let mut score = 0;
-
-	// We like corners because they open up the ability to make diagonals,
-	// something which top mid, bot mid and the two sides dont let us do
-	score += board.corners(Slot::X);
-	score -= board.corners(Slot::O);
-
-	if turn == Slot::X {
-		score += 6 * board.one_aways_x();
-		score += 3 * board.one_aways_o();
-	} else if turn == Slot::O {
-		score -= 6 * board.one_aways_o();
-		score -= 3 * board.one_aways_x();
-	}
+	score_with(board, turn, &EvalParams::DEFAULT)
+}
 
-	score
+#[inline]
+pub fn score_with(board: BitBoard, turn: Slot, params: &EvalParams) -> i32 {
+	BoardTerms::new(board).score(turn, params)
 }
 
 #[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
@@ -240,38 +329,3 @@ pub fn possible_to_win(board: BitBoard) -> bool {
 		idx += 16
 	}
 }
-
-#[inline]
-#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
-unsafe fn possible_to_win(board: BitBoard) -> bool {
-	use core::arch::x86_64::{
-		_mm256_and_si256, _mm256_cmpeq_epi32, _mm256_loadu_si256, _mm256_movemask_epi8,
-		_mm256_or_si256, _mm256_set1_epi32,
-	};
-
-	let brd = _mm256_set1_epi32(board.0 as i32);
-	let mut idx = 0;
-
-	loop {
-		if idx >= 88 {
-			break false;
-		}
-
-		let masks0 = _mm256_loadu_si256(POSSIBLE_TO_WIN.as_ptr().add(idx) as *const __m256i);
-		let masks1 = _mm256_loadu_si256(POSSIBLE_TO_WIN.as_ptr().add(idx + 8) as *const __m256i);
-
-		let and0 = _mm256_and_si256(masks0, brd);
-		let and1 = _mm256_and_si256(masks1, brd);
-
-		let eqs0 = _mm256_cmpeq_epi32(and0, masks0);
-		let eqs1 = _mm256_cmpeq_epi32(and1, masks1);
-
-		let comb = _mm256_or_si256(eqs0, eqs1);
-
-		if _mm256_movemask_epi8(comb) != 0 {
-			return true;
-		}
-
-		idx += 16;
-	}
-}
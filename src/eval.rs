@@ -0,0 +1,806 @@
+use std::{fmt::Display, path::Path};
+
+use crate::{
+	bitboard::BitBoard,
+	board::{Slot, State},
+	counting::{LINES_THROUGH, score_game_with},
+	game::{Game, Undo},
+	lut,
+	moves::Move,
+};
+
+/// Anything the search can use to score positions, `turn` is the side which made the last move
+pub trait Evaluator: Sync {
+	/// Whatever is kept between moves so a leaf doesn't have to be scored from scratch
+	type Cache: Clone;
+
+	/// Score of `game` from X's point of view
+	fn score_game(&self, game: &Game, turn: Slot) -> i32;
+
+	fn new_cache(&self, game: &Game) -> Self::Cache;
+
+	/// Brings `cache` up to date with `game`, which just had the move `undo` was returned for made
+	fn update_cache(&self, cache: &mut Self::Cache, game: &Game, undo: &Undo);
+
+	/// Same as `score_game` but using an up to date `cache`
+	fn score_cached(&self, cache: &Self::Cache, game: &Game, turn: Slot) -> i32;
+
+	/// Cheap guess at how good `mv` is for `side` without making it, higher is better. Orders
+	/// moves deeper in the search where scoring every child is too costly
+	#[inline]
+	fn order_hint(&self, _cache: &Self::Cache, _mv: Move, _side: Slot) -> i32 {
+		0
+	}
+}
+
+/// Weights used by `score_game`/`score`, the defaults are the hand-picked values the engine has
+/// always used.
+///
+/// Can be loaded from a simple TOML-like file of `key = value` lines, `#` starts a comment and
+/// `[section]` headers are ignored:
+/// ```text
+/// meta_weight = 100
+/// board_divisor = 4
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EvalParams {
+	/// Multiplier for the score of the shrunken meta-board
+	pub meta_weight: i32,
+	/// Each sub-board's score is divided by this before being summed
+	pub board_divisor: i32,
+	/// Added for every sub-board won by X, subtracted for every one won by O
+	pub won_board: i32,
+	/// Weight of the one-aways belonging to `turn`
+	pub own_one_away: i32,
+	/// Weight of the one-aways belonging to the side opposite of `turn`
+	pub opp_one_away: i32,
+	/// Weight of each corner held
+	pub corner: i32,
+	/// When the next player gets a free move the score moves by `scr / free_move_divisor`
+	pub free_move_divisor: i32,
+	/// Weight of each meta line X can still complete minus those O can
+	pub live_line: i32,
+	/// Weight of a one-away on an open board, for each live meta line running through that board
+	pub line_board: i32,
+	/// Penalty for sending the other player to a board they can win immediately
+	pub send_win: i32,
+	/// Penalty for each live meta line of the other player through the board they're sent to
+	pub send_danger: i32,
+	/// Penalty for giving the other player a free move
+	pub send_free: i32,
+	/// Score of a board with three in a row, positive when X has it
+	pub win: i32,
+}
+
+impl EvalParams {
+	pub const DEFAULT: EvalParams = EvalParams {
+		meta_weight: 100,
+		board_divisor: 4,
+		won_board: 100,
+		own_one_away: 6,
+		opp_one_away: 3,
+		corner: 1,
+		free_move_divisor: 3,
+		live_line: 20,
+		line_board: 4,
+		send_win: 30,
+		send_danger: 5,
+		send_free: 15,
+		win: 10_000,
+	};
+
+	/// Names of the weights, in the order used by `get`/`get_mut`
+	pub const NAMES: [&'static str; 13] = [
+		"meta_weight",
+		"board_divisor",
+		"won_board",
+		"own_one_away",
+		"opp_one_away",
+		"corner",
+		"free_move_divisor",
+		"live_line",
+		"line_board",
+		"send_win",
+		"send_danger",
+		"send_free",
+		"win",
+	];
+
+	pub const fn get(&self, idx: usize) -> i32 {
+		match idx {
+			0 => self.meta_weight,
+			1 => self.board_divisor,
+			2 => self.won_board,
+			3 => self.own_one_away,
+			4 => self.opp_one_away,
+			5 => self.corner,
+			6 => self.free_move_divisor,
+			7 => self.live_line,
+			8 => self.line_board,
+			9 => self.send_win,
+			10 => self.send_danger,
+			11 => self.send_free,
+			12 => self.win,
+
+			_ => panic!("weight index out of range"),
+		}
+	}
+
+	pub const fn get_mut(&mut self, idx: usize) -> &mut i32 {
+		match idx {
+			0 => &mut self.meta_weight,
+			1 => &mut self.board_divisor,
+			2 => &mut self.won_board,
+			3 => &mut self.own_one_away,
+			4 => &mut self.opp_one_away,
+			5 => &mut self.corner,
+			6 => &mut self.free_move_divisor,
+			7 => &mut self.live_line,
+			8 => &mut self.line_board,
+			9 => &mut self.send_win,
+			10 => &mut self.send_danger,
+			11 => &mut self.send_free,
+			12 => &mut self.win,
+
+			_ => panic!("weight index out of range"),
+		}
+	}
+
+	/// Whether `idx` is used as a divisor and so must never be 0
+	pub const fn is_divisor(idx: usize) -> bool {
+		idx == 1 || idx == 6
+	}
+
+	/// Parses a weight file, any weight not mentioned keeps its default value
+	pub fn parse(src: &str) -> Result<EvalParams, &'static str> {
+		let mut params = EvalParams::DEFAULT;
+
+		for line in src.lines() {
+			let line = line.split('#').next().unwrap().trim();
+
+			if line.is_empty() || line.starts_with('[') {
+				continue;
+			}
+
+			let Some((key, val)) = line.split_once('=') else {
+				return Err("expected a `key = value` line");
+			};
+
+			let Some(idx) = Self::NAMES.iter().position(|n| *n == key.trim()) else {
+				return Err("unknown weight name");
+			};
+
+			let Ok(val) = val.trim().parse() else {
+				return Err("weight must be an integer");
+			};
+
+			if Self::is_divisor(idx) && val == 0 {
+				return Err("divisors cannot be 0");
+			}
+
+			*params.get_mut(idx) = val;
+		}
+
+		Ok(params)
+	}
+
+	pub fn load(path: impl AsRef<Path>) -> Result<EvalParams, &'static str> {
+		let src = std::fs::read_to_string(path).map_err(|_| "could not read weight file")?;
+
+		Self::parse(&src)
+	}
+}
+
+impl Evaluator for EvalParams {
+	type Cache = ScoreCache;
+
+	#[inline]
+	fn score_game(&self, game: &Game, turn: Slot) -> i32 {
+		score_game_with(game, turn, self)
+	}
+
+	fn new_cache(&self, game: &Game) -> ScoreCache {
+		ScoreCache::new(game, self)
+	}
+
+	#[inline]
+	fn update_cache(&self, cache: &mut ScoreCache, game: &Game, undo: &Undo) {
+		cache.update(game, undo.mv.game(), self);
+	}
+
+	#[inline]
+	fn score_cached(&self, cache: &ScoreCache, game: &Game, turn: Slot) -> i32 {
+		cache.score_game(game.active, turn, self)
+	}
+
+	/// Sending the other player somewhere they can do less is better
+	#[inline]
+	fn order_hint(&self, cache: &ScoreCache, mv: Move, side: Slot) -> i32 {
+		-cache.terms.dest_penalty(mv.index(), side, self)
+	}
+}
+
+impl Default for EvalParams {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
+
+/// Writes the weights in the same format `parse` reads
+impl Display for EvalParams {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (idx, name) in Self::NAMES.iter().enumerate() {
+			writeln!(f, "{name} = {}", self.get(idx))?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Everything `score` looks at on a single board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardTerms {
+	pub state: State,
+	/// Whoever has three in a row on the board, `Slot::Empty` if no one does
+	pub won_by: Slot,
+	pub corners_x: i32,
+	pub corners_o: i32,
+	pub one_aways_x: i32,
+	pub one_aways_o: i32,
+}
+
+impl BoardTerms {
+	pub fn new(board: BitBoard) -> BoardTerms {
+		let won_by = if lut::won_by_x(board) {
+			Slot::X
+		} else if lut::won_by_o(board) {
+			Slot::O
+		} else {
+			Slot::Empty
+		};
+
+		BoardTerms {
+			state: board.state(),
+			won_by,
+			corners_x: board.corners(Slot::X),
+			corners_o: board.corners(Slot::O),
+			one_aways_x: lut::one_aways_x(board),
+			one_aways_o: lut::one_aways_o(board),
+		}
+	}
+
+	/// `score_with` of the board these terms came from
+	#[inline]
+	pub fn score(&self, turn: Slot, params: &EvalParams) -> i32 {
+		match self.won_by {
+			Slot::X => return params.win,
+			Slot::O => return -params.win,
+			_ => {}
+		}
+
+		let mut score = 0;
+
+		// We like corners because they open up the ability to make diagonals,
+		// something which top mid, bot mid and the two sides dont let us do
+		score += params.corner * self.corners_x;
+		score -= params.corner * self.corners_o;
+
+		if turn == Slot::X {
+			score += params.own_one_away * self.one_aways_x;
+			score += params.opp_one_away * self.one_aways_o;
+		} else if turn == Slot::O {
+			score -= params.own_one_away * self.one_aways_o;
+			score -= params.opp_one_away * self.one_aways_x;
+		}
+
+		score
+	}
+}
+
+/// The terms of every board and of the shrunken meta-board, which is all `score_game` needs
+/// besides `active`. A move only changes the board it was played in (and the meta-board if that
+/// board got decided), so the search updates this instead of rescoring all ten boards per leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalCache {
+	pub boards: [BoardTerms; 9],
+	pub meta: BoardTerms,
+	/// `live_lines`, which only changes when a board is decided
+	live: (u8, u8),
+}
+
+impl EvalCache {
+	pub fn new(game: &Game) -> EvalCache {
+		Self::from_terms(
+			game.boards.map(BoardTerms::new),
+			BoardTerms::new(game.shrink()),
+		)
+	}
+
+	pub fn from_terms(boards: [BoardTerms; 9], meta: BoardTerms) -> EvalCache {
+		EvalCache {
+			live: Self::find_live_lines(&boards),
+			boards,
+			meta,
+		}
+	}
+
+	/// Updates the cache after a move was played in board `bdx` of `game`
+	#[inline]
+	pub fn update(&mut self, game: &Game, bdx: u8) {
+		let old = self.boards[bdx as usize].state;
+		self.boards[bdx as usize] = BoardTerms::new(game.boards[bdx as usize]);
+
+		if self.boards[bdx as usize].state != old {
+			self.meta = BoardTerms::new(game.shrink());
+			self.live = Self::find_live_lines(&self.boards);
+		}
+	}
+
+	/// Bitmasks of the meta lines X and O can each still complete, a line is dead for a side once
+	/// any board on it is won by the other side or tied
+	#[inline]
+	pub fn live_lines(&self) -> (u8, u8) {
+		self.live
+	}
+
+	fn find_live_lines(boards: &[BoardTerms; 9]) -> (u8, u8) {
+		let mut dead_x = 0;
+		let mut dead_o = 0;
+
+		for (idx, brd) in boards.iter().enumerate() {
+			match brd.state {
+				State::Won => dead_o |= LINES_THROUGH[idx],
+				State::Lost => dead_x |= LINES_THROUGH[idx],
+				State::Tied => {
+					dead_x |= LINES_THROUGH[idx];
+					dead_o |= LINES_THROUGH[idx];
+				}
+				State::Undecided => {}
+			}
+		}
+
+		(!dead_x, !dead_o)
+	}
+
+	/// Line-aware meta-board term: rewards having more live meta lines than the opponent and
+	/// being close to winning the open boards on them, weighted by how many live lines run
+	/// through each
+	pub fn line_score(&self, params: &EvalParams) -> i32 {
+		let (live_x, live_o) = self.live;
+		let mut scr = params.live_line * (live_x.count_ones() as i32 - live_o.count_ones() as i32);
+
+		for idx in 0..9 {
+			scr += self.line_term(idx, params);
+		}
+
+		scr
+	}
+
+	/// What board `idx` adds to `line_score`
+	#[inline]
+	fn line_term(&self, idx: usize, params: &EvalParams) -> i32 {
+		let brd = &self.boards[idx];
+
+		if brd.state != State::Undecided {
+			return 0;
+		}
+
+		let (live_x, live_o) = self.live;
+		let lines_x = (LINES_THROUGH[idx] & live_x).count_ones() as i32;
+		let lines_o = (LINES_THROUGH[idx] & live_o).count_ones() as i32;
+
+		params.line_board * (lines_x * brd.one_aways_x - lines_o * brd.one_aways_o)
+	}
+
+	/// What `brd` adds to `score_game` on its own
+	#[inline]
+	fn board_term(brd: &BoardTerms, turn: Slot, params: &EvalParams) -> i32 {
+		let won = match brd.state {
+			State::Won => params.won_board,
+			State::Lost => -params.won_board,
+			_ => 0,
+		};
+
+		brd.score(turn, params) / params.board_divisor + won
+	}
+
+	/// How bad it is for `side` to send the other player to board `dest`, always positive
+	pub fn dest_penalty(&self, dest: u8, side: Slot, params: &EvalParams) -> i32 {
+		let brd = &self.boards[dest as usize];
+
+		if brd.state != State::Undecided {
+			return params.send_free;
+		}
+
+		let (live_x, live_o) = self.live_lines();
+		let (opp_one_aways, opp_live) = match side {
+			Slot::X => (brd.one_aways_o, live_o),
+			_ => (brd.one_aways_x, live_x),
+		};
+
+		let mut penalty =
+			params.send_danger * (LINES_THROUGH[dest as usize] & opp_live).count_ones() as i32;
+
+		if opp_one_aways > 0 {
+			penalty += params.send_win;
+		}
+
+		penalty
+	}
+
+	/// Send-aware term: `turn` just sent the other player to `active`, which is worse for `turn`
+	/// the more the other player can do there
+	pub fn send_score(&self, active: u8, turn: Slot, params: &EvalParams) -> i32 {
+		let penalty = if active == 9 {
+			params.send_free
+		} else {
+			self.dest_penalty(active, turn, params)
+		};
+
+		match turn {
+			Slot::X => -penalty,
+			Slot::O => penalty,
+			_ => 0,
+		}
+	}
+
+	/// `score_game_with` for the game these terms came from
+	pub fn score_game(&self, active: u8, turn: Slot, params: &EvalParams) -> i32 {
+		let boards = self
+			.boards
+			.iter()
+			.map(|brd| Self::board_term(brd, turn, params))
+			.sum();
+
+		self.total(boards, self.line_score(params), active, turn, params)
+	}
+
+	/// `score_game` given the sum of the `board_term`s and the `line_score`
+	#[inline]
+	fn total(&self, boards: i32, lines: i32, active: u8, turn: Slot, params: &EvalParams) -> i32 {
+		let mut scr = self.meta.score(turn, params) * params.meta_weight + boards + lines;
+
+		if active == 9 && turn == Slot::X {
+			scr -= scr / params.free_move_divisor;
+		} else if active == 9 && turn == Slot::O {
+			scr += scr / params.free_move_divisor;
+		}
+
+		scr + self.send_score(active, turn, params)
+	}
+}
+
+/// `EvalCache` along with the sums `score_game` adds up for one set of weights, kept up to date
+/// move by move so scoring a leaf doesn't look at every board. This is the search's cache for
+/// `EvalParams`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreCache {
+	pub terms: EvalCache,
+	/// Sum of every `board_term` with X and with O as `turn`
+	boards: [i32; 2],
+	/// `EvalCache::line_score`
+	lines: i32,
+}
+
+impl ScoreCache {
+	pub fn new(game: &Game, params: &EvalParams) -> ScoreCache {
+		let terms = EvalCache::new(game);
+
+		ScoreCache {
+			boards: [Slot::X, Slot::O].map(|turn| {
+				terms
+					.boards
+					.iter()
+					.map(|brd| EvalCache::board_term(brd, turn, params))
+					.sum()
+			}),
+			lines: terms.line_score(params),
+			terms,
+		}
+	}
+
+	/// Updates the cache after a move was played in board `bdx` of `game`
+	#[inline]
+	pub fn update(&mut self, game: &Game, bdx: u8, params: &EvalParams) {
+		let idx = bdx as usize;
+		let old = self.terms.boards[idx];
+		let old_line = self.terms.line_term(idx, params);
+
+		self.terms.update(game, bdx);
+		let new = &self.terms.boards[idx];
+
+		for (sum, turn) in self.boards.iter_mut().zip([Slot::X, Slot::O]) {
+			*sum += EvalCache::board_term(new, turn, params)
+				- EvalCache::board_term(&old, turn, params);
+		}
+
+		// Deciding a board can change which lines are live for every other board
+		if new.state != old.state {
+			self.lines = self.terms.line_score(params);
+		} else {
+			self.lines += self.terms.line_term(idx, params) - old_line;
+		}
+	}
+
+	/// `EvalCache::score_game`, `params` have to be the ones the cache was made with
+	#[inline]
+	pub fn score_game(&self, active: u8, turn: Slot, params: &EvalParams) -> i32 {
+		let boards = match turn {
+			Slot::X => self.boards[0],
+			Slot::O => self.boards[1],
+			_ => return self.terms.score_game(active, turn, params),
+		};
+
+		self.terms.total(boards, self.lines, active, turn, params)
+	}
+}
+
+/// Breakdown of `score_game`, every term is from X's point of view and they add up to `total`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+	pub turn: Slot,
+	/// The shrunken meta-board, tied boards show up as having no cells
+	pub meta: BoardTerms,
+	/// `score` of the meta-board
+	pub meta_score: i32,
+	/// `meta_score * meta_weight`
+	pub meta_term: i32,
+	pub boards: [BoardTerms; 9],
+	/// `score` of every board
+	pub board_scores: [i32; 9],
+	/// `board_scores[n] / board_divisor`
+	pub board_terms: [i32; 9],
+	pub boards_won: i32,
+	pub boards_lost: i32,
+	/// `(boards_won - boards_lost) * won_board`
+	pub won_term: i32,
+	/// Number of meta lines each side can still complete
+	pub live_lines_x: u32,
+	pub live_lines_o: u32,
+	/// The line-aware meta-board term, see `EvalCache::line_score`
+	pub line_term: i32,
+	/// Adjustment for the next player getting a free move
+	pub free_move: i32,
+	/// How good the board the next player was sent to is for `turn`, see `EvalCache::send_score`
+	pub send_term: i32,
+	pub total: i32,
+}
+
+impl EvalParams {
+	/// Same as `score_game_with` but keeps every term
+	pub fn explain(&self, game: &Game, turn: Slot) -> Explanation {
+		let cache = EvalCache::new(game);
+		let (boards, meta) = (cache.boards, cache.meta);
+
+		let meta_score = meta.score(turn, self);
+		let board_scores = boards.map(|b| b.score(turn, self));
+		let board_terms = board_scores.map(|scr| scr / self.board_divisor);
+
+		let boards_won = boards.iter().filter(|b| b.state == State::Won).count() as i32;
+		let boards_lost = boards.iter().filter(|b| b.state == State::Lost).count() as i32;
+
+		let meta_term = meta_score * self.meta_weight;
+		let won_term = (boards_won - boards_lost) * self.won_board;
+		let (live_x, live_o) = cache.live_lines();
+		let line_term = cache.line_score(self);
+		let scr = meta_term + board_terms.iter().sum::<i32>() + won_term + line_term;
+
+		let free_move = match turn {
+			Slot::X if game.active == 9 => -(scr / self.free_move_divisor),
+			Slot::O if game.active == 9 => scr / self.free_move_divisor,
+			_ => 0,
+		};
+
+		let send_term = cache.send_score(game.active, turn, self);
+
+		Explanation {
+			turn,
+			meta,
+			meta_score,
+			meta_term,
+			boards,
+			board_scores,
+			board_terms,
+			boards_won,
+			boards_lost,
+			won_term,
+			live_lines_x: live_x.count_ones(),
+			live_lines_o: live_o.count_ones(),
+			line_term,
+			free_move,
+			send_term,
+			total: scr + free_move + send_term,
+		}
+	}
+}
+
+/// Breakdown of `score_game` with the default weights
+pub fn explain(game: &Game, turn: Slot) -> Explanation {
+	EvalParams::DEFAULT.explain(game, turn)
+}
+
+impl Display for Explanation {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let row = |f: &mut std::fmt::Formatter<'_>,
+		           name: &str,
+		           brd: &BoardTerms,
+		           score: i32,
+		           term: i32| {
+			let state = match brd.state {
+				State::Won => "won by X",
+				State::Lost => "won by O",
+				State::Tied => "tied",
+				State::Undecided => "open",
+			};
+			let corners = format!("{}/{}", brd.corners_x, brd.corners_o);
+			let one_aways = format!("{}/{}", brd.one_aways_x, brd.one_aways_o);
+
+			writeln!(
+				f,
+				"{name:<6}{state:<10}{corners:>8}{one_aways:>11}{score:>8}{term:>8}"
+			)
+		};
+
+		writeln!(f, "evaluation after {} moved (+ is good for X)", self.turn)?;
+		writeln!(
+			f,
+			"{:<6}{:<10}{:>8}{:>11}{:>8}{:>8}",
+			"board", "state", "corners", "one-aways", "score", "term"
+		)?;
+
+		for idx in 0..9 {
+			let name = ((idx as u8 + b'A') as char).to_string();
+
+			row(
+				f,
+				&name,
+				&self.boards[idx],
+				self.board_scores[idx],
+				self.board_terms[idx],
+			)?;
+		}
+		row(f, "meta", &self.meta, self.meta_score, self.meta_term)?;
+
+		writeln!(
+			f,
+			"boards won/lost: {}/{} -> {}",
+			self.boards_won, self.boards_lost, self.won_term
+		)?;
+		writeln!(
+			f,
+			"live meta lines X/O: {}/{} -> {}",
+			self.live_lines_x, self.live_lines_o, self.line_term
+		)?;
+		writeln!(f, "free move adjustment: {}", self.free_move)?;
+		writeln!(f, "sent to: {}", self.send_term)?;
+		write!(f, "total: {}", self.total)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{EvalCache, EvalParams, Evaluator, ScoreCache, explain};
+	use crate::{
+		board::{Slot, State},
+		counting::score_game,
+		game::Game,
+		moves::{Move, legal_moves},
+	};
+
+	#[test]
+	fn round_trip() {
+		let mut params = EvalParams::DEFAULT;
+		params.corner = 7;
+		params.won_board = -20;
+		params.win = 5_000;
+
+		assert_eq!(EvalParams::parse(&params.to_string()), Ok(params));
+	}
+
+	#[test]
+	fn parse_partial() {
+		let src = "[eval]\n# comment\nmeta_weight = 50 # trailing\n\n";
+		let params = EvalParams::parse(src).unwrap();
+
+		assert_eq!(params.meta_weight, 50);
+		assert_eq!(params.board_divisor, EvalParams::DEFAULT.board_divisor);
+	}
+
+	#[test]
+	fn parse_errors() {
+		assert!(EvalParams::parse("nonsense = 1").is_err());
+		assert!(EvalParams::parse("corner 1").is_err());
+		assert!(EvalParams::parse("corner = one").is_err());
+		assert!(EvalParams::parse("board_divisor = 0").is_err());
+	}
+
+	#[test]
+	fn cache_matches_fresh() {
+		for seed in 0..20 {
+			let mut game = Game::new();
+			let mut cache = EvalCache::new(&game);
+			let mut scores = ScoreCache::new(&game, &EvalParams::DEFAULT);
+
+			for ply in 0.. {
+				let lgms = legal_moves(&game);
+
+				if lgms.is_empty() || game.state() != State::Undecided {
+					break;
+				}
+
+				let mv = lgms[(seed * 31 + ply * 7) % lgms.len()];
+				let side = game.to_move().slot();
+				game.make_move(mv).unwrap();
+				cache.update(&game, mv.game());
+				scores.update(&game, mv.game(), &EvalParams::DEFAULT);
+
+				assert_eq!(cache, EvalCache::new(&game));
+				assert_eq!(scores, ScoreCache::new(&game, &EvalParams::DEFAULT));
+				assert_eq!(
+					cache.score_game(game.active, side, &EvalParams::DEFAULT),
+					score_game(&game, side)
+				);
+
+				for turn in [Slot::X, Slot::O] {
+					assert_eq!(
+						scores.score_game(game.active, turn, &EvalParams::DEFAULT),
+						score_game(&game, turn)
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn explanation_matches_score() {
+		for times in [0, 5, 20, 40, 60] {
+			let game = Game::random(times);
+
+			for turn in [Slot::X, Slot::O] {
+				assert_eq!(explain(&game, turn).total, score_game(&game, turn));
+			}
+		}
+
+		let expl = explain(&Game::_test(), Slot::X);
+		assert_eq!(expl.boards[1].state, State::Won);
+		assert_eq!((expl.boards_won, expl.boards_lost), (1, 1));
+	}
+
+	#[test]
+	fn tied_boards_block_lines() {
+		let mut game = Game::new();
+		game.boards[4].set_state(State::Tied);
+		game.boards[0].set_state(State::Won);
+
+		let expl = explain(&game, Slot::X);
+
+		// Center is on 4 lines, the top left corner on 3 (one shared with the center)
+		assert_eq!(expl.live_lines_x, 4);
+		assert_eq!(expl.live_lines_o, 2);
+	}
+
+	#[test]
+	fn sending_to_a_winnable_board() {
+		use crate::bitboard::BitBoard;
+		use Slot::{Empty as E, O};
+
+		let mut game = Game::new();
+		game.boards[4] = BitBoard::new_with([O, E, O, E, E, E, E, E, E]);
+		game.active = 4;
+
+		let params = EvalParams::DEFAULT;
+		let expl = explain(&game, Slot::X);
+
+		assert_eq!(expl.send_term, -(params.send_win + 4 * params.send_danger));
+		assert_eq!(explain(&game, Slot::O).send_term, 4 * params.send_danger);
+
+		// Ordering puts sending O to the board it can win behind sending it anywhere else
+		let cache = params.new_cache(&game);
+		assert!(
+			params.order_hint(&cache, Move::new(4, 4), Slot::X)
+				< params.order_hint(&cache, Move::new(4, 0), Slot::X)
+		);
+	}
+}
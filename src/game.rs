@@ -5,21 +5,36 @@ use rand::{SeedableRng, seq::IndexedRandom};
 use crate::{
 	bitboard::{
 		BitBoard,
-		consts::{UN_MASK, UN_OFFS},
+		consts::{E_MASK, E_OFFS, ST_MASK, TURN_MASK, UN_MASK, UN_OFFS},
 	},
-	board::{Slot, State},
-	counting::possible_to_win,
-	moves::{Move, is_legal, legal_moves},
+	board::{Player, Slot, State},
+	lut,
+	moves::{Move, MoveError, Squares, generate_moves, is_legal},
+	notation::NotationError,
 };
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct Game {
-	// Game state is stored in upper 3 bits of `boards[0]`
+	// Game state is stored in bits 29-30 of `boards[0]` and the side to move in bit 31
 	pub boards: [BitBoard; 9],
 	/// Indicates active board, 0-8 is the idx, 9 means any board is free
 	pub active: u8,
 }
 
+/// Everything `make_move` overwrites, enough for `unmake_move` to restore the game exactly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+	pub mv: Move,
+	/// The side that made the move
+	pub side: Player,
+	/// `active` before the move
+	pub active: u8,
+	/// The board that was played in before the move, including its state bits
+	pub board: BitBoard,
+	/// State of the whole game before the move
+	pub state: State,
+}
+
 /// Template for board, a-i represents which board
 /// x means insert fmt char, y means insert reset char
 const TEMPLATE: &[u8] = b"
@@ -46,6 +61,19 @@ const TEMPLATE: &[u8] = b"
   \x1b[0;3tm\x1b[0;3Gmg\x1b[0;3tm | \x1b[0;3Gmg\x1b[0;3tm | \x1b[0;3Gmg\x1b[0;3tm\x1b[0m   |   \x1b[0;3um\x1b[0;3Hmh\x1b[0;3um | \x1b[0;3Hmh\x1b[0;3um | \x1b[0;3Hmh\x1b[0;3um\x1b[0m   |   \x1b[0;3vm\x1b[0;3Imi\x1b[0;3vm | \x1b[0;3Imi\x1b[0;3vm | \x1b[0;3Imi\x1b[0;3vm\x1b[0m   
 ";
 
+/// The state a board ends up in after its last move, see `Game::make_move`
+pub(crate) fn decided_state(board: BitBoard) -> State {
+	if lut::won_by_x(board) {
+		State::Won
+	} else if lut::won_by_o(board) {
+		State::Lost
+	} else if !lut::possible_to_win(board) {
+		State::Tied
+	} else {
+		State::Undecided
+	}
+}
+
 impl Game {
 	pub const fn new() -> Self {
 		let mut g = Game {
@@ -54,13 +82,14 @@ impl Game {
 		};
 
 		g.set_state(State::Undecided);
+		g.set_to_move(Player::X);
 
 		g
 	}
 
 	#[inline]
 	pub const fn state(&self) -> State {
-		State::from_u32(self.boards[0].0 >> UN_OFFS)
+		State::from_u32((self.boards[0].0 & UN_MASK) >> UN_OFFS)
 	}
 
 	#[inline]
@@ -69,21 +98,70 @@ impl Game {
 		self.boards[0].0 |= st.to_u32() << UN_OFFS;
 	}
 
+	/// The side whose turn it is, X makes the first move
+	#[inline]
+	pub const fn to_move(&self) -> Player {
+		if self.boards[0].0 & TURN_MASK != 0 {
+			Player::O
+		} else {
+			Player::X
+		}
+	}
+
+	/// The turn bit is set when O is to move, so a new game and every position with X to move
+	/// have the same bits they had before the turn was tracked
+	#[inline]
+	pub const fn set_to_move(&mut self, side: Player) {
+		self.boards[0].0 &= !TURN_MASK;
+
+		if let Player::O = side {
+			self.boards[0].0 |= TURN_MASK;
+		}
+	}
+
+	/// Plays `moves` from the start of the game, each of which has to be legal
+	pub fn from_moves(moves: impl IntoIterator<Item = Move>) -> Result<Game, MoveError> {
+		let mut game = Game::new();
+
+		for mv in moves {
+			game.make_move(mv)?;
+		}
+
+		Ok(game)
+	}
+
+	/// A position from its boards (state bits included), which has to pass `validate`. `active`
+	/// is 0-8 or 9 for any board, and the state of the whole game is worked out
+	pub fn from_boards(
+		boards: [BitBoard; 9],
+		to_move: Player,
+		active: u8,
+	) -> Result<Game, NotationError> {
+		let mut game = Game::new();
+
+		game.boards = boards;
+		game.active = active;
+		game.set_state(decided_state(game.shrink()));
+		game.set_to_move(to_move);
+
+		game.validate()
+			.map_err(|errs| NotationError::Invalid(errs[0]))?;
+
+		Ok(game)
+	}
+
 	pub fn random(times: u8) -> Game {
 		let mut g = Game::new();
 		let mut rng = rand::rngs::SmallRng::seed_from_u64(42);
-		let mut side = Slot::X;
 
 		for _ in 0..times {
-			let lgms = legal_moves(&g);
+			let lgms = generate_moves(&g);
 
-			if lgms.is_empty() {
+			if lgms.is_empty() || g.state() != State::Undecided {
 				break;
 			}
 
-			g.make_move(*lgms.choose(&mut rng).unwrap(), side).unwrap();
-
-			side = side.flip();
+			g.make_move(*lgms.choose(&mut rng).unwrap()).unwrap();
 		}
 
 		g
@@ -92,18 +170,15 @@ impl Game {
 	pub fn random_seedless(times: u8) -> Game {
 		let mut g = Game::new();
 		let mut rng = rand::rng();
-		let mut side = Slot::X;
 
 		for _ in 0..times {
-			let lgms = legal_moves(&g);
+			let lgms = generate_moves(&g);
 
-			if lgms.is_empty() {
+			if lgms.is_empty() || g.state() != State::Undecided {
 				break;
 			}
 
-			g.make_move(*lgms.choose(&mut rng).unwrap(), side).unwrap();
-
-			side = side.flip();
+			g.make_move(*lgms.choose(&mut rng).unwrap()).unwrap();
 		}
 
 		g
@@ -123,6 +198,7 @@ impl Game {
 		// Loss Board
 		g.boards[2] = BitBoard::new_with([O, O, O, E, E, E, E, E, E]);
 		g.boards[2].set_state(State::Lost);
+		g.set_to_move(Player::O);
 
 		g
 	}
@@ -135,6 +211,7 @@ impl Game {
 		}
 
 		new.set_state(new.state().flip());
+		new.set_to_move(self.to_move().flip());
 
 		new
 	}
@@ -150,50 +227,114 @@ impl Game {
 		BitBoard::new_with(arr)
 	}
 
-	pub fn sim_move(&self, mv: Move, side: Slot) -> Result<Game, &'static str> {
+	/// Every legal square as a bit (see `Move::square`), taken from the empty cells of the
+	/// undecided boards the next move can go in. Empty once the game is over
+	pub fn legal_mask(&self) -> u128 {
+		let mut mask = 0;
+
+		if self.state() != State::Undecided {
+			return mask;
+		}
+
+		let boards = if self.active == 9 {
+			0..9
+		} else {
+			self.active..self.active + 1
+		};
+
+		for bdx in boards {
+			let brd = self.boards[bdx as usize];
+
+			if brd.0 & ST_MASK == 0 {
+				mask |= (((brd.0 & E_MASK) >> E_OFFS) as u128) << (bdx * 9);
+			}
+		}
+
+		mask
+	}
+
+	/// The moves in `legal_mask`
+	#[inline]
+	pub fn legal_squares(&self) -> Squares {
+		Squares(self.legal_mask())
+	}
+
+	pub fn sim_move(&self, mv: Move) -> Result<Game, MoveError> {
 		let mut new = self.clone();
 
-		new.make_move(mv, side)?;
+		new.make_move(mv)?;
 
 		Ok(new)
 	}
 
-	pub fn make_move(&mut self, mv: Move, side: Slot) -> Result<(), &'static str> {
+	/// Plays `mv` for the side to move
+	pub fn make_move(&mut self, mv: Move) -> Result<Undo, MoveError> {
 		is_legal(self, mv)?;
 
-		if self.active == 9 {
-			self.active = mv.game();
+		// Only positions that were valid to begin with have to stay valid, the boards are public
+		let was_valid = cfg!(debug_assertions) && self.validate().is_ok();
+		let undo = self.make_move_unchecked(mv);
+		debug_assert!(
+			!was_valid || self.validate().is_ok(),
+			"{:?}",
+			self.validate()
+		);
+
+		Ok(undo)
+	}
+
+	/// `make_move`, but only if it's `side`'s turn
+	pub fn make_move_as(&mut self, mv: Move, side: Player) -> Result<Undo, MoveError> {
+		if side != self.to_move() {
+			return Err(MoveError::WrongTurn);
 		}
 
+		self.make_move(mv)
+	}
+
+	/// `make_move` without checking that `mv` is legal, for the search which only plays moves it
+	/// got from `generate_moves`
+	#[inline]
+	pub fn make_move_unchecked(&mut self, mv: Move) -> Undo {
+		let side = self.to_move();
+		let undo = Undo {
+			mv,
+			side,
+			active: self.active,
+			board: self.boards[mv.game() as usize],
+			state: self.state(),
+		};
+
 		let brd = &mut self.boards[mv.game() as usize];
 
 		brd.0 |= 1
 			<< (mv.index()
 				+ match side {
-					Slot::X => 0,
-					Slot::O => 9,
-					Slot::Empty => 18,
-					Slot::Disabled => unreachable!(),
+					Player::X => 0,
+					Player::O => 9,
 				});
 
 		let idx = 1 << (18 + mv.index());
 		brd.0 &= !idx;
 
-		if brd.won_by_x() {
+		if lut::won_by_x(*brd) {
 			self.boards[mv.game() as usize].set_state(State::Won);
-		} else if brd.won_by_o() {
+		} else if lut::won_by_o(*brd) {
 			self.boards[mv.game() as usize].set_state(State::Lost);
-		} else if !possible_to_win(*brd) {
+		} else if !lut::possible_to_win(*brd) {
 			self.boards[mv.game() as usize].set_state(State::Tied);
 		}
 
-		let shrunken = self.shrink();
-		if shrunken.won_by_x() {
-			self.set_state(State::Won);
-		} else if shrunken.won_by_o() {
-			self.set_state(State::Lost);
-		} else if !possible_to_win(shrunken) {
-			self.set_state(State::Tied);
+		// The game can only be decided by deciding a board
+		if self.boards[mv.game() as usize].state() != undo.board.state() {
+			let shrunken = self.shrink();
+			if lut::won_by_x(shrunken) {
+				self.set_state(State::Won);
+			} else if lut::won_by_o(shrunken) {
+				self.set_state(State::Lost);
+			} else if !lut::possible_to_win(shrunken) {
+				self.set_state(State::Tied);
+			}
 		}
 
 		if self.boards[mv.index() as usize].state() != State::Undecided {
@@ -202,7 +343,18 @@ impl Game {
 			self.active = mv.index();
 		}
 
-		Ok(())
+		self.set_to_move(side.flip());
+
+		undo
+	}
+
+	/// Takes back the move `undo` was returned for, which has to be the last move made
+	#[inline]
+	pub fn unmake_move(&mut self, undo: Undo) {
+		self.boards[undo.mv.game() as usize] = undo.board;
+		self.active = undo.active;
+		self.set_state(undo.state);
+		self.set_to_move(undo.side);
 	}
 
 	/// Maps a state to an ASCII color index
@@ -262,7 +414,12 @@ impl Default for Game {
 
 #[cfg(test)]
 mod test {
-	use crate::{board::State, game::Game};
+	use crate::{
+		bitboard::BitBoard,
+		board::{Player, Slot, State},
+		game::Game,
+		moves::{Move, legal_moves},
+	};
 
 	#[test]
 	fn modify_state() {
@@ -273,4 +430,89 @@ mod test {
 		assert_eq!(game.state(), State::Won);
 		assert_eq!(game.boards[0].state(), State::Undecided);
 	}
+
+	#[test]
+	fn unmake_restores() {
+		for times in [0, 10, 30, 50] {
+			let start = Game::random(times);
+
+			for mv in legal_moves(&start) {
+				let mut game = start.clone();
+				let undo = game.make_move(mv).unwrap();
+
+				assert_ne!(game, start);
+
+				game.unmake_move(undo);
+				assert_eq!(game, start);
+			}
+		}
+	}
+
+	#[test]
+	fn unmake_restores_state_bits() {
+		use Slot::{Empty as E, O, X};
+
+		// X wins the whole game by completing board 0, whose upper bits hold the game state
+		let mut start = Game::new();
+		for bdx in [0, 1, 2] {
+			start.boards[bdx] = BitBoard::new_with([X, X, E, O, O, E, E, E, E]);
+		}
+		start.boards[1].set_state(State::Won);
+		start.boards[2].set_state(State::Won);
+		start.active = 0;
+		start.set_to_move(Player::X);
+
+		let mut game = start.clone();
+		let undo = game.make_move(Move::new(0, 2)).unwrap();
+
+		assert_eq!(game.boards[0].state(), State::Won);
+		assert_eq!(game.state(), State::Won);
+
+		game.unmake_move(undo);
+		assert_eq!(game, start);
+		assert_eq!(game.state(), State::Undecided);
+		assert_eq!(game.boards[0].0, start.boards[0].0);
+	}
+
+	#[test]
+	fn unmake_restores_notation() {
+		// The same win as above, from a position that could come up in a game
+		let start = Game::from_notation(
+			"xxx....../xxx....../xx.oo..../..o....o./o......../....o..../..o....../\
+			o......../......... xx------- x c",
+		)
+		.unwrap();
+
+		let mut game = start.clone();
+		let undo = game.make_move(Move::new(2, 2)).unwrap();
+		assert_eq!(game.state(), State::Won);
+
+		game.unmake_move(undo);
+		assert_eq!(game.to_notation(), start.to_notation());
+		assert_eq!(game, start);
+	}
+
+	#[test]
+	fn turns_alternate() {
+		let mut game = Game::new();
+		assert_eq!(game.to_move(), Player::X);
+
+		assert!(game.make_move_as(Move::new(4, 4), Player::O).is_err());
+		assert_eq!(game, Game::new());
+
+		let undo = game.make_move_as(Move::new(4, 4), Player::X).unwrap();
+		assert_eq!(game.to_move(), Player::O);
+		assert_eq!(game.boards[4].to_arr()[4], Slot::X);
+		assert_eq!(game.state(), State::Undecided);
+
+		game.make_move(Move::new(4, 0)).unwrap();
+		assert_eq!(game.to_move(), Player::X);
+		assert_eq!(game.boards[4].to_arr()[0], Slot::O);
+		assert_eq!(game.flip().to_move(), Player::O);
+
+		let mut game = Game::new();
+		game.make_move(Move::new(4, 4)).unwrap();
+		game.unmake_move(undo);
+		assert_eq!(game, Game::new());
+	}
 }
@@ -1,9 +1,24 @@
 //! So that the benchmark can access these
 
+pub mod batch;
 pub mod bitboard;
 pub mod board;
+pub mod codec;
 pub mod counting;
+pub mod eval;
 pub mod game;
 pub mod generated;
+pub mod lut;
 pub mod moves;
+pub mod nn;
+pub mod notation;
 pub mod openingbook;
+pub mod packed;
+pub mod pgn;
+pub mod record;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod simd;
+pub mod td;
+pub mod tuner;
+pub mod validate;
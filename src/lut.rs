@@ -0,0 +1,270 @@
+//! Lookup tables for the per-board checks in `BitBoard` and `counting`
+//!
+//! Whether a board is won, how many one-aways it has and whether it can still be won only depend
+//! on which cells are X, O and empty, so they can be read from tables instead of scanning the
+//! masks in `generated.rs`. The tables are built at compile time from those same masks and the
+//! tests check they agree with the scanning versions on every board.
+
+use crate::{
+	bitboard::BitBoard,
+	generated::{POSSIBLE_TO_WIN, WON_BY_X},
+};
+
+/// How many entries of `POSSIBLE_TO_WIN` `counting::possible_to_win` looks at
+const POSSIBLE_MASKS: usize = 88;
+
+/// Whether any of `masks` has all its bits set in `board`
+const fn any_match(board: u32, masks: &[u32]) -> bool {
+	let mut idx = 0;
+
+	while idx < masks.len() {
+		if masks[idx] & board == masks[idx] {
+			return true;
+		}
+
+		idx += 1;
+	}
+
+	false
+}
+
+/// Indexed by the 9 cells of one side, whether they have three in a row
+pub static WON: [bool; 512] = {
+	let mut table = [false; 512];
+	let mut bits = 0;
+
+	while bits < 512 {
+		table[bits] = any_match(bits as u32, &WON_BY_X);
+
+		bits += 1;
+	}
+
+	table
+};
+
+/// The three cells of every line in `WON_BY_X`
+const LINE_CELLS: [[u32; 3]; 8] = {
+	let mut cells = [[0; 3]; 8];
+	let mut line = 0;
+
+	while line < 8 {
+		let mut mask = WON_BY_X[line];
+		let mut n = 0;
+
+		while mask != 0 {
+			cells[line][n] = mask.trailing_zeros();
+			mask &= mask - 1;
+			n += 1;
+		}
+
+		line += 1;
+	}
+
+	cells
+};
+
+/// Which 3 of a line's cells belong to the side, as bits 0-2 in `LINE_CELLS` order
+const fn line_pattern(own: u32, line: usize) -> usize {
+	let [a, b, c] = LINE_CELLS[line];
+
+	(((own >> a) & 1) | (((own >> b) & 1) << 1) | (((own >> c) & 1) << 2)) as usize
+}
+
+/// For every line, a bitset of the patterns (see `line_pattern`) of a line made of only the
+/// side's cells and empty cells which `counting::possible_to_win` counts as winnable. Scanning
+/// the masks once per pattern here is much cheaper than once per board in `lines`
+const fn winnable_patterns<const FOR_X: bool>() -> [u8; 8] {
+	let possible = POSSIBLE_TO_WIN.split_at(POSSIBLE_MASKS).0;
+	let mut patterns = [0; 8];
+	let mut line = 0;
+
+	while line < 8 {
+		let mut pattern = 0;
+
+		while pattern < 8 {
+			let mut own = 0;
+			let mut cell = 0;
+
+			while cell < 3 {
+				if pattern & (1 << cell) != 0 {
+					own |= 1 << LINE_CELLS[line][cell];
+				}
+
+				cell += 1;
+			}
+
+			let empty = WON_BY_X[line] & !own;
+			let board = if FOR_X {
+				own | (empty << 18)
+			} else {
+				(own << 9) | (empty << 18)
+			};
+
+			if any_match(board, possible) {
+				patterns[line] |= 1 << pattern;
+			}
+
+			pattern += 1;
+		}
+
+		line += 1;
+	}
+
+	patterns
+}
+
+/// Bits 0-3 are the number of one-aways, bit 4 is set if the side can still complete a line.
+/// Entries where the side's cells and the empty cells overlap can't happen and are left at 0
+const fn lines<const FOR_X: bool>() -> [u8; 1 << 18] {
+	let patterns = winnable_patterns::<FOR_X>();
+
+	let mut table = [0; 1 << 18];
+	let mut own = 0;
+
+	while own < 512 {
+		let free = !own & 0x1FF;
+		let mut empty = free;
+
+		// Every subset of the cells `own` doesn't take, from `free` down to 0
+		loop {
+			let mut count = 0;
+			let mut winnable = 0;
+			let mut line = 0;
+
+			while line < 8 {
+				let mask = WON_BY_X[line];
+
+				if (own | empty) & mask == mask {
+					let pattern = line_pattern(own, line);
+
+					// Two of the side's cells and an empty one
+					count += (pattern.count_ones() == 2) as u8;
+					winnable |= (patterns[line] >> pattern) & 1;
+				}
+
+				line += 1;
+			}
+
+			table[(own | (empty << 9)) as usize] = count | (winnable << 4);
+
+			if empty == 0 {
+				break;
+			}
+
+			empty = (empty - 1) & free;
+		}
+
+		own += 1;
+	}
+
+	table
+}
+
+/// Indexed by `x | empty << 9`, see `lines`. The two tables are 256 KiB each but only the entries
+/// real boards index get touched, and `benches/lut.rs` has them about 3x faster than scanning for
+/// one-aways and 8x for `possible_to_win`
+pub static X_LINES: [u8; 1 << 18] = lines::<true>();
+/// Indexed by `o | empty << 9`, see `lines`
+pub static O_LINES: [u8; 1 << 18] = lines::<false>();
+
+#[inline]
+pub fn won(bits: u16) -> bool {
+	WON[bits as usize & 0x1FF]
+}
+
+/// `possible_to_win` for a board with X on `x`, O on `o` and `empty` empty, any other cells are
+/// disabled
+#[inline]
+pub fn winnable(x: u16, o: u16, empty: u16) -> bool {
+	let e = (empty as usize & 0x1FF) << 9;
+
+	(X_LINES[x as usize & 0x1FF | e] | O_LINES[o as usize & 0x1FF | e]) & 0x10 != 0
+}
+
+#[inline]
+const fn x_bits(board: BitBoard) -> u16 {
+	(board.0 & 0x1FF) as u16
+}
+
+#[inline]
+const fn o_bits(board: BitBoard) -> u16 {
+	((board.0 >> 9) & 0x1FF) as u16
+}
+
+#[inline]
+const fn e_bits(board: BitBoard) -> u16 {
+	((board.0 >> 18) & 0x1FF) as u16
+}
+
+#[inline]
+pub fn won_by_x(board: BitBoard) -> bool {
+	won(x_bits(board))
+}
+
+#[inline]
+pub fn won_by_o(board: BitBoard) -> bool {
+	won(o_bits(board))
+}
+
+#[inline]
+pub fn one_aways_x(board: BitBoard) -> i32 {
+	(X_LINES[x_bits(board) as usize | (e_bits(board) as usize) << 9] & 0xF) as i32
+}
+
+#[inline]
+pub fn one_aways_o(board: BitBoard) -> i32 {
+	(O_LINES[o_bits(board) as usize | (e_bits(board) as usize) << 9] & 0xF) as i32
+}
+
+#[inline]
+pub fn possible_to_win(board: BitBoard) -> bool {
+	winnable(x_bits(board), o_bits(board), e_bits(board))
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		bitboard::BitBoard,
+		board::Slot::{self, Disabled, Empty, O, X},
+		counting,
+	};
+
+	/// Every board including disabled cells, as found on the meta-board
+	fn all_boards() -> impl Iterator<Item = BitBoard> {
+		(0..4u32.pow(9)).map(|mut n| {
+			let cells: [Slot; 9] = std::array::from_fn(|_| {
+				let cell = [Empty, X, O, Disabled][n as usize % 4];
+				n /= 4;
+
+				cell
+			});
+
+			BitBoard::new_with(cells)
+		})
+	}
+
+	#[test]
+	fn matches_scanning() {
+		for brd in all_boards() {
+			assert_eq!(super::won_by_x(brd), brd.won_by_x(), "{brd:?}");
+			assert_eq!(super::won_by_o(brd), brd.won_by_o(), "{brd:?}");
+			assert_eq!(super::one_aways_x(brd), brd.one_aways_x(), "{brd:?}");
+			assert_eq!(super::one_aways_o(brd), brd.one_aways_o(), "{brd:?}");
+			assert_eq!(
+				super::possible_to_win(brd),
+				counting::possible_to_win(brd),
+				"{brd:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn ignores_state_bits() {
+		let mut brd = BitBoard::new_with([X, X, Empty, O, O, Empty, Empty, Empty, Empty]);
+		let plain = brd;
+		brd.0 |= 0b111 << 27;
+
+		assert_eq!(super::one_aways_x(brd), super::one_aways_x(plain));
+		assert_eq!(super::possible_to_win(brd), super::possible_to_win(plain));
+	}
+}
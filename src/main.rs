@@ -1,46 +1,91 @@
 use mimalloc::MiMalloc;
 use ultimengine::{
-	board::{Slot, State},
-	counting::engine_mv,
+	board::{Player, State},
+	counting::engine_mv_with,
+	eval::{EvalParams, Evaluator},
 	game::Game,
-	moves::{Move, parse_move},
+	moves::{Move, parse_move, parse_numpad},
+	nn::Network,
+	pgn::Pgn,
+	record::GameRecord,
 };
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-fn redraw(game: &Game, last_mv: Option<Move>) {
+fn redraw(game: &Game, last_mv: Option<Move>, human: Player) {
 	print!("\x1B[2J\x1B[1;1H");
 	println!("{}", game.print(last_mv));
 
-	match game.state() {
-		State::Won => println!("YOU HAVE LOST!!!!!"),
-		State::Lost => println!("YOU HAVE WON!!!!!"),
-		State::Tied => println!("tie game :("),
+	let winner = match game.state() {
+		State::Won => Player::X,
+		State::Lost => Player::O,
+		State::Tied => {
+			println!("tie game :(");
+			std::process::exit(1);
+		}
 		State::Undecided => return,
+	};
+
+	if winner == human {
+		println!("YOU HAVE WON!!!!!");
+	} else {
+		println!("YOU HAVE LOST!!!!!");
 	}
 
 	std::process::exit(1);
 }
 
 fn main() {
-	let stdin = std::io::stdin();
+	let fail = |path: &str, e: &str| -> ! {
+		eprintln!("{path}: {e}");
+		std::process::exit(1);
+	};
+
+	let mut params = EvalParams::DEFAULT;
+	let mut net = None;
+
+	if let Some(path) = std::env::args().nth(1) {
+		if path.ends_with(".nn") {
+			net = Some(Network::load(&path).unwrap_or_else(|e| fail(&path, e)));
+		} else {
+			params = EvalParams::load(&path).unwrap_or_else(|e| fail(&path, e));
+		}
+	}
 
-	let mut game = Game::new();
+	// The search is generic over the evaluator, so the choice is made once here. It's only asked
+	// for a move while the game is undecided (`redraw` exits after that), so there's always one
+	let engine: Box<dyn Fn(&Game) -> Move> = match &net {
+		Some(net) => Box::new(|game| engine_mv_with(game, net).unwrap()),
+		None => Box::new(|game| engine_mv_with(game, &params).unwrap()),
+	};
+
+	let stdin = std::io::stdin();
+	let mut record = GameRecord::new();
+	// You play whoever is to move at the start and after a `load`, the engine plays the other side
+	let mut human = record.game().to_move();
 
 	let mut mov_buf = String::new();
-	let mut last_g = Game::new();
-	let mut last_mv = None;
+	// Whether cells are read laid out like a numpad rather than like a phone
+	let mut numpad = false;
 
 	loop {
-		redraw(&game, last_mv);
+		redraw(record.game(), record.last_move(), human);
+
+		if record.game().to_move() != human {
+			let mv = engine(record.game());
+
+			record.make_move(mv).unwrap();
+
+			continue;
+		}
 
 		print!(
 			"Enter your move (ex. a5, active board: {}): ",
-			if game.active == 9 {
+			if record.game().active == 9 {
 				' '
 			} else {
-				(game.active + b'a') as char
+				(record.game().active + b'a') as char
 			}
 		);
 
@@ -51,17 +96,77 @@ fn main() {
 		stdin.read_line(&mut mov_buf).unwrap();
 
 		match mov_buf.trim() {
-			"undo" => std::mem::swap(&mut game, &mut last_g),
-			"skip" => {}
-			_ => {
-				let mv = parse_move(mov_buf.trim(), game.active).and_then(|mv| {
-					let r = game.make_move(mv, Slot::O);
-					if r.is_ok() {
-						last_mv = Some(mv);
+			// Back to the last position where it was your move, or forward to the next one
+			"undo" => {
+				while record.undo().is_some() && record.game().to_move() != human {}
+
+				continue;
+			}
+			"redo" => {
+				while record.redo().is_some() && record.game().to_move() != human {}
+
+				continue;
+			}
+			"skip" => record.set_to_move(human.flip()),
+			"numpad" => {
+				numpad = !numpad;
+
+				continue;
+			}
+			"explain" => {
+				// `turn` is the side that made the last move
+				let turn = record.game().to_move().flip().slot();
+
+				// The breakdown is of `params`, which a network doesn't use
+				match &net {
+					None => println!("{}", params.explain(record.game(), turn)),
+					Some(net) => println!(
+						"explain only breaks down weight files, the network scores this at {}",
+						net.score_game(record.game(), turn)
+					),
+				}
+				println!("(press enter to continue)");
+
+				stdin.read_line(&mut mov_buf).unwrap();
+
+				continue;
+			}
+			"position" => {
+				println!("{}", record.game().to_notation());
+				println!("(press enter to continue)");
+
+				stdin.read_line(&mut mov_buf).unwrap();
+
+				continue;
+			}
+			cmd if cmd.starts_with("save ") => {
+				let path = cmd["save ".len()..].trim();
+				let msg = save(&record, human, path)
+					.map_or_else(|e| format!("\x1b[0;31m{e}"), |_| format!("saved to {path}"));
+
+				println!("{msg} (press enter to continue)\x1b[0m");
+				stdin.read_line(&mut mov_buf).unwrap();
+
+				continue;
+			}
+			cmd if cmd.starts_with("load ") => {
+				match Game::from_notation(&cmd["load ".len()..]) {
+					Ok(game) => {
+						record = GameRecord::from_game(game);
+						human = record.game().to_move();
+					}
+					Err(e) => {
+						println!("\x1b[0;31m{e} (press enter to continue)\x1b[0m");
+						stdin.read_line(&mut mov_buf).unwrap();
 					}
+				}
 
-					r
-				});
+				continue;
+			}
+			_ => {
+				let parse = if numpad { parse_numpad } else { parse_move };
+				let mv = parse(mov_buf.trim(), record.game().active)
+					.and_then(|mv| record.make_move_as(mv, human));
 
 				if let Err(e) = mv {
 					println!("\x1b[0;31m{e} (press enter to continue)\x1b[0m");
@@ -75,17 +180,20 @@ fn main() {
 				}
 			}
 		}
+	}
+}
 
-		redraw(&game, last_mv);
-
-		let mv = engine_mv(&game);
-
-		last_g = game.clone();
-
-		game.make_move(mv, Slot::X).unwrap();
+/// Writes the game so far to `path` as a game record (see `pgn`)
+fn save(record: &GameRecord, human: Player, path: &str) -> Result<(), String> {
+	let mut pgn = Pgn::from_record(record);
+	let (x, o) = match human {
+		Player::X => ("human", "ultimengine"),
+		Player::O => ("ultimengine", "human"),
+	};
 
-		last_mv = Some(mv);
+	pgn.set_tag("X", x);
+	pgn.set_tag("O", o);
+	pgn.set_tag("Result", pgn.result.as_str());
 
-		redraw(&game, last_mv);
-	}
+	std::fs::write(path, pgn.to_string()).map_err(|e| e.to_string())
 }
@@ -1,7 +1,11 @@
-use std::fmt::{Debug, Display};
+use std::{
+	fmt::{Debug, Display},
+	ops::{Deref, DerefMut},
+	str::FromStr,
+};
 
 use crate::{
-	bitboard::consts::{ST_MASK, ST_OFFS},
+	bitboard::consts::{E_MASK, E_OFFS, ST_MASK, ST_OFFS},
 	board::State,
 	game::Game,
 };
@@ -12,15 +16,92 @@ use crate::{
 /// game  idx
 pub struct Move(pub(crate) u8);
 
+/// Index of one of the 9 boards of the game, 0-8 from the top left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubBoard(u8);
+
+/// Index of one of the 9 cells of a board, 0-8 from the top left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cell(u8);
+
+impl SubBoard {
+	#[inline]
+	pub const fn new(n: u8) -> Option<Self> {
+		if n < 9 { Some(SubBoard(n)) } else { None }
+	}
+
+	#[inline]
+	pub const fn get(self) -> u8 {
+		self.0
+	}
+}
+
+impl Cell {
+	#[inline]
+	pub const fn new(n: u8) -> Option<Self> {
+		if n < 9 { Some(Cell(n)) } else { None }
+	}
+
+	#[inline]
+	pub const fn get(self) -> u8 {
+		self.0
+	}
+}
+
+impl TryFrom<u8> for SubBoard {
+	type Error = MoveError;
+
+	fn try_from(n: u8) -> Result<Self, MoveError> {
+		SubBoard::new(n).ok_or(MoveError::OutOfRange)
+	}
+}
+
+impl TryFrom<u8> for Cell {
+	type Error = MoveError;
+
+	fn try_from(n: u8) -> Result<Self, MoveError> {
+		Cell::new(n).ok_or(MoveError::OutOfRange)
+	}
+}
+
 impl Move {
+	/// Both have to be within 0-8 or this panics, use `try_new` for values that haven't been
+	/// checked
+	#[inline]
 	pub fn new(game: u8, index: u8) -> Self {
-		let idx_b = index & 0b00001111;
-		let gam_b = (game & 0b00001111) << 4;
-		Move(idx_b | gam_b)
+		match Move::try_new(game, index) {
+			Ok(mv) => mv,
+			Err(err) => panic!("{err} (got {game} and {index})"),
+		}
+	}
+
+	pub fn try_new(game: u8, index: u8) -> Result<Self, MoveError> {
+		Ok(Move::from_parts(game.try_into()?, index.try_into()?))
 	}
 
-	pub fn from_raw(n: u8) -> Self {
-		Move(n)
+	#[inline]
+	pub const fn from_parts(board: SubBoard, cell: Cell) -> Self {
+		Move((board.0 << 4) | cell.0)
+	}
+
+	/// The move in the layout of `Move`, if both halves are within 0-8
+	pub const fn from_raw(n: u8) -> Option<Self> {
+		if n >> 4 < 9 && n & 0b00001111 < 9 {
+			Some(Move(n))
+		} else {
+			None
+		}
+	}
+
+	/// Every way of making or changing a move checks both halves, so this can't be out of range
+	#[inline]
+	pub const fn sub_board(&self) -> SubBoard {
+		SubBoard(self.game())
+	}
+
+	#[inline]
+	pub const fn cell(&self) -> Cell {
+		Cell(self.index())
 	}
 
 	#[inline]
@@ -29,11 +110,9 @@ impl Move {
 	}
 
 	#[inline]
-	pub const fn set_game(&mut self, idx: u8) {
-		debug_assert!(idx <= 9);
-
+	pub const fn set_game(&mut self, board: SubBoard) {
 		self.0 &= 0b00001111;
-		self.0 |= (idx & 0b00001111) << 4;
+		self.0 |= board.0 << 4;
 	}
 
 	#[inline]
@@ -42,74 +121,275 @@ impl Move {
 	}
 
 	#[inline]
-	pub const fn set_idx(&mut self, idx: u8) {
-		debug_assert!(idx <= 9);
-
+	pub const fn set_idx(&mut self, cell: Cell) {
 		self.0 &= 0b11110000;
-		self.0 |= idx & 0b00001111;
+		self.0 |= cell.0;
+	}
+
+	/// Index of the square across the whole game, `game * 9 + index` (0-80)
+	#[inline]
+	pub const fn square(&self) -> u8 {
+		self.game() * 9 + self.index()
+	}
+
+	/// The move at `sq`, if it's within 0-80
+	#[inline]
+	pub const fn from_square(sq: u8) -> Option<Self> {
+		if sq < 81 {
+			Some(Move::square_unchecked(sq))
+		} else {
+			None
+		}
+	}
+
+	#[inline]
+	const fn square_unchecked(sq: u8) -> Self {
+		Move(((sq / 9) << 4) | (sq % 9))
 	}
 }
 
+/// Iterator over the moves in a mask of squares (see `Move::square`), like `Game::legal_mask`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Squares(pub(crate) u128);
+
+impl Squares {
+	/// The moves in `mask`, leaving out any bits past square 80
+	#[inline]
+	pub const fn new(mask: u128) -> Self {
+		Squares(mask & ((1 << 81) - 1))
+	}
+}
+
+impl Iterator for Squares {
+	type Item = Move;
+
+	#[inline]
+	fn next(&mut self) -> Option<Move> {
+		if self.0 == 0 {
+			return None;
+		}
+
+		let sq = self.0.trailing_zeros() as u8;
+		self.0 &= self.0 - 1;
+
+		// `new` and `Game::legal_mask` leave the bits past 80 clear
+		Some(Move::square_unchecked(sq))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = self.0.count_ones() as usize;
+
+		(len, Some(len))
+	}
+}
+
+impl ExactSizeIterator for Squares {}
+
 impl Display for Move {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "{}{}", (self.game() + b'A') as char, self.index() + 1)
 	}
 }
 
+/// Why a move couldn't be parsed or played
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveError {
+	/// The board has already been won or tied
+	BoardFinished,
+	SquareOccupied,
+	/// The move has to be in board `active`
+	WrongBoard {
+		active: u8,
+	},
+	/// The whole game has already been won or tied
+	GameOver,
+	/// It's the other side's turn
+	WrongTurn,
+	/// Not in any of the notations `Move::from_str` and `parse_move` read
+	ParseFormat,
+	/// The 1 char shorthand needs an active board to play in
+	ParseShorthand,
+	/// The board letter isn't within a to i
+	ParseBoard,
+	/// The square isn't a digit from 1 to 9
+	ParseIndex,
+	/// A global row or column isn't within 0 to 8
+	ParseCoord,
+	/// A global square isn't within 0 to 80
+	ParseSquare,
+	/// A board or cell index isn't within 0 to 8
+	OutOfRange,
+	/// Only X and O have turns, not an empty or disabled cell
+	NotASide,
+}
+
+impl MoveError {
+	pub const fn message(&self) -> &'static str {
+		match self {
+			MoveError::BoardFinished => "That game has been finished",
+			MoveError::SquareOccupied => "square is not empty",
+			MoveError::WrongBoard { .. } => "must play in the active board",
+			MoveError::GameOver => "the game is already over",
+			MoveError::WrongTurn => "it is not that side's turn",
+			MoveError::ParseFormat => "moves look like a5, 4,4 (row,column) or 40 (square)",
+			MoveError::ParseShorthand => {
+				"Can only use shorthand notation when a specific board is active"
+			}
+			MoveError::ParseBoard => "game must be within a to i",
+			MoveError::ParseIndex => "index must be between 1 and 9",
+			MoveError::ParseCoord => "row and column must be between 0 and 8",
+			MoveError::ParseSquare => "square must be between 0 and 80",
+			MoveError::OutOfRange => "board and cell must be between 0 and 8",
+			MoveError::NotASide => "only x and o can make moves",
+		}
+	}
+
+	/// Stable identifier of the error for front-ends that talk to the engine over a protocol
+	pub const fn code(&self) -> &'static str {
+		match self {
+			MoveError::BoardFinished => "board_finished",
+			MoveError::SquareOccupied => "square_occupied",
+			MoveError::WrongBoard { .. } => "wrong_board",
+			MoveError::GameOver => "game_over",
+			MoveError::WrongTurn => "wrong_turn",
+			MoveError::ParseFormat => "parse_format",
+			MoveError::ParseShorthand => "parse_shorthand",
+			MoveError::ParseBoard => "parse_board",
+			MoveError::ParseIndex => "parse_index",
+			MoveError::ParseCoord => "parse_coord",
+			MoveError::ParseSquare => "parse_square",
+			MoveError::OutOfRange => "out_of_range",
+			MoveError::NotASide => "not_a_side",
+		}
+	}
+}
+
+impl Display for MoveError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			MoveError::WrongBoard { active } => {
+				write!(f, "{} ({})", self.message(), (active + b'a') as char)
+			}
+			_ => f.write_str(self.message()),
+		}
+	}
+}
+
+impl std::error::Error for MoveError {}
+
 #[inline]
-pub fn is_legal(game: &Game, mv: Move) -> Result<(), &'static str> {
+pub fn is_legal(game: &Game, mv: Move) -> Result<(), MoveError> {
+	if game.state() != State::Undecided {
+		return Err(MoveError::GameOver);
+	}
+
 	if game.boards[mv.game() as usize].state() != State::Undecided {
-		return Err("That game has been finished");
+		return Err(MoveError::BoardFinished);
 	}
 
 	let idx = 1 << (18 + mv.index());
 	if game.boards[mv.game() as usize].0 & idx != idx {
-		return Err("square is not empty");
+		return Err(MoveError::SquareOccupied);
 	}
 
 	if game.active != mv.game() && game.active != 9 {
-		return Err("must play in the active board");
+		return Err(MoveError::WrongBoard {
+			active: game.active,
+		});
 	}
 
 	Ok(())
 }
 
-// Game, Idx
-pub fn parse_move(input: &str, active: u8) -> Result<Move, &'static str> {
-	if input.len() > 2 || input.is_empty() {
-		return Err("Move string must be 1 or 2 chars");
+/// Board letter, a-i in either case
+fn parse_board(c: u8) -> Result<u8, MoveError> {
+	match c.to_ascii_lowercase() {
+		c @ b'a'..=b'i' => Ok(c - b'a'),
+		_ => Err(MoveError::ParseBoard),
 	}
+}
 
-	if active == 9 && input.len() == 1 {
-		return Err("Can only use shorthand notation when a specific board is active");
+/// Square digit, 1-9
+fn parse_index(c: u8) -> Result<u8, MoveError> {
+	match c {
+		b'1'..=b'9' => Ok(c - b'1'),
+		_ => Err(MoveError::ParseIndex),
 	}
+}
 
-	if input.len() == 1 && active != 9 {
-		return Ok(Move::new(active, input.as_bytes()[0] - b'0' - 1));
+/// A number made of ASCII digits only, unlike `u8::from_str` which also takes a `+`
+fn parse_number(s: &str) -> Option<u8> {
+	if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) {
+		return None;
 	}
 
-	// Purposefully Invalid sentinel
-	// game, idx
-	let mut mov = Move::new(active, 9);
+	s.parse().ok()
+}
 
-	let bs = input.as_bytes();
+/// Reads `A5` as printed by `Display` (the board letter in either case), global `row,col`
+/// coordinates (0-8 each, from the top left) or a global square (`0` to `80`, see
+/// `Move::square`). Only plain ASCII digits count as numbers, so no signs
+impl FromStr for Move {
+	type Err = MoveError;
 
-	if input.len() == 2 {
-		if !('a'..='i').contains(&char::from(bs[0])) {
-			return Err("game must be within a to i");
-		};
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
 
-		let v = bs[0] - b'a';
-		mov.set_game(v);
+		if let Some((row, col)) = s.split_once(',') {
+			let coord = |n: &str| match parse_number(n.trim()) {
+				Some(n) if n < 9 => Ok(n),
+				_ => Err(MoveError::ParseCoord),
+			};
+			let (row, col) = (coord(row)?, coord(col)?);
+
+			return Ok(Move::new(row / 3 * 3 + col / 3, row % 3 * 3 + col % 3));
+		}
+
+		if !s.is_empty() && s.bytes().all(|c| c.is_ascii_digit()) {
+			return parse_number(s)
+				.and_then(Move::from_square)
+				.ok_or(MoveError::ParseSquare);
+		}
+
+		match *s.as_bytes() {
+			[board, idx] if board.is_ascii_alphabetic() => {
+				Ok(Move::new(parse_board(board)?, parse_index(idx)?))
+			}
+			_ => Err(MoveError::ParseFormat),
+		}
 	}
+}
 
-	if !char::from(bs[bs.len() - 1]).is_ascii_digit() {
-		return Err("index must be between 1 and 9");
-	};
+/// `Move::from_str`, except that a lone digit is always a cell of the active board rather
+/// than a global square
+pub fn parse_move(input: &str, active: u8) -> Result<Move, MoveError> {
+	let input = input.trim();
 
-	mov.set_idx(bs[bs.len() - 1] - b'0' - 1);
+	if let [idx] = *input.as_bytes() {
+		if active == 9 {
+			return Err(MoveError::ParseShorthand);
+		}
+
+		return Ok(Move::new(active, parse_index(idx)?));
+	}
 
-	Ok(mov)
+	input.parse()
+}
+
+/// Where each digit of a numpad is on a board, 7 8 9 is the top row and 1 2 3 the bottom one
+const NUMPAD: [u8; 9] = [6, 7, 8, 3, 4, 5, 0, 1, 2];
+
+/// `parse_move` with the square digit of `a5` and the shorthand laid out like a numpad
+pub fn parse_numpad(input: &str, active: u8) -> Result<Move, MoveError> {
+	let input = input.trim();
+	let mv = parse_move(input, active)?;
+
+	match *input.as_bytes() {
+		[_] | [b'a'..=b'z' | b'A'..=b'Z', _] => {
+			Ok(Move::new(mv.game(), NUMPAD[mv.index() as usize]))
+		}
+		_ => Ok(mv),
+	}
 }
 
 #[inline]
@@ -127,9 +407,15 @@ pub fn fast_legal(game: &Game, mv: Move) -> bool {
 	!(not_active || in_occupied || in_finished)
 }
 
+/// Checks every square with `fast_legal`, `generate_moves` is the faster way to get the same list.
+/// Empty once the game is over
 pub fn legal_moves(game: &Game) -> Vec<Move> {
 	let mut mvs = Vec::with_capacity(80);
 
+	if game.state() != State::Undecided {
+		return mvs;
+	}
+
 	for bdx in 0..9 {
 		for idx in 0..9 {
 			let m = Move::new(bdx, idx);
@@ -142,3 +428,295 @@ pub fn legal_moves(game: &Game) -> Vec<Move> {
 
 	mvs
 }
+
+/// At most every square can be legal, which only happens on the first move
+pub const MAX_MOVES: usize = 81;
+
+/// Fixed capacity list of moves which lives on the stack, derefs to a slice of the moves in it
+#[derive(Clone, Copy)]
+pub struct MoveList {
+	moves: [Move; MAX_MOVES],
+	len: u8,
+}
+
+impl MoveList {
+	pub const fn new() -> Self {
+		MoveList {
+			moves: [Move(0); MAX_MOVES],
+			len: 0,
+		}
+	}
+
+	#[inline]
+	pub fn push(&mut self, mv: Move) {
+		debug_assert!((self.len as usize) < MAX_MOVES);
+
+		self.moves[self.len as usize] = mv;
+		self.len += 1;
+	}
+
+	#[inline]
+	pub fn clear(&mut self) {
+		self.len = 0;
+	}
+}
+
+impl Default for MoveList {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Deref for MoveList {
+	type Target = [Move];
+
+	#[inline]
+	fn deref(&self) -> &[Move] {
+		&self.moves[..self.len as usize]
+	}
+}
+
+impl DerefMut for MoveList {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut [Move] {
+		&mut self.moves[..self.len as usize]
+	}
+}
+
+impl Debug for MoveList {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_list().entries(self.iter()).finish()
+	}
+}
+
+impl PartialEq for MoveList {
+	fn eq(&self, other: &Self) -> bool {
+		**self == **other
+	}
+}
+
+impl Eq for MoveList {}
+
+impl IntoIterator for MoveList {
+	type Item = Move;
+	type IntoIter = std::iter::Take<std::array::IntoIter<Move, MAX_MOVES>>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.moves.into_iter().take(self.len as usize)
+	}
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+	type Item = &'a Move;
+	type IntoIter = std::slice::Iter<'a, Move>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+/// Same moves in the same order as `legal_moves`, without allocating. Reads the empty cells of
+/// each board straight from its bits, skips decided boards and only looks at the active board
+/// when there is one
+#[inline]
+pub fn generate_moves(game: &Game) -> MoveList {
+	let mut list = MoveList::new();
+
+	if game.state() != State::Undecided {
+		return list;
+	}
+
+	let boards = if game.active == 9 {
+		0..9
+	} else {
+		game.active..game.active + 1
+	};
+
+	for bdx in boards {
+		let brd = game.boards[bdx as usize];
+
+		if brd.0 & ST_MASK != 0 {
+			continue;
+		}
+
+		let mut empty = (brd.0 & E_MASK) >> E_OFFS;
+
+		while empty != 0 {
+			// Both come from a board's bits, so they're within range
+			list.push(Move((bdx << 4) | empty.trailing_zeros() as u8));
+
+			empty &= empty - 1;
+		}
+	}
+
+	list
+}
+
+#[cfg(test)]
+mod test {
+	use super::{
+		Cell, Move, MoveError, Squares, SubBoard, generate_moves, legal_moves, parse_move,
+		parse_numpad,
+	};
+	use crate::{
+		board::{Player, Slot, State},
+		game::Game,
+	};
+
+	#[test]
+	fn generate_matches_legal() {
+		for times in 0..70 {
+			let game = Game::random(times);
+
+			assert_eq!(*generate_moves(&game), *legal_moves(&game));
+		}
+
+		let game = Game::_test();
+		assert_eq!(*generate_moves(&game), *legal_moves(&game));
+	}
+
+	#[test]
+	fn finished_games_have_no_moves() {
+		let mut game = Game::new().sim_move(Move::new(4, 4)).unwrap();
+
+		for state in [State::Won, State::Lost, State::Tied] {
+			game.set_state(state);
+
+			assert!(generate_moves(&game).is_empty());
+			assert!(legal_moves(&game).is_empty());
+			assert_eq!(game.legal_mask(), 0);
+		}
+	}
+
+	#[test]
+	fn squares_round_trip() {
+		for sq in 0..81 {
+			let mv = Move::from_square(sq).unwrap();
+
+			assert_eq!(mv.square(), sq);
+			assert_eq!(mv, Move::new(sq / 9, sq % 9));
+		}
+	}
+
+	#[test]
+	fn mask_matches_generate() {
+		for times in 0..70 {
+			let game = Game::random(times);
+			let mask = game.legal_mask();
+
+			assert_eq!(mask.count_ones() as usize, generate_moves(&game).len());
+			assert_eq!(
+				game.legal_squares().collect::<Vec<_>>(),
+				*generate_moves(&game)
+			);
+		}
+	}
+
+	#[test]
+	fn move_errors() {
+		let mut game = Game::new();
+		game.make_move(Move::new(4, 4)).unwrap();
+
+		assert_eq!(
+			game.sim_move(Move::new(4, 4)),
+			Err(MoveError::SquareOccupied)
+		);
+		assert_eq!(
+			game.sim_move(Move::new(0, 0)),
+			Err(MoveError::WrongBoard { active: 4 })
+		);
+		assert_eq!(
+			game.make_move_as(Move::new(4, 0), Player::X).unwrap_err(),
+			MoveError::WrongTurn
+		);
+
+		assert_eq!(
+			Game::_test().sim_move(Move::new(0, 8)),
+			Err(MoveError::BoardFinished)
+		);
+
+		game.set_state(State::Won);
+		assert_eq!(game.sim_move(Move::new(4, 0)), Err(MoveError::GameOver));
+
+		assert_eq!(parse_move("a12", 9), Err(MoveError::ParseFormat));
+		assert_eq!(parse_move("5", 9), Err(MoveError::ParseShorthand));
+		assert_eq!(parse_move("z5", 9), Err(MoveError::ParseBoard));
+		assert_eq!(parse_move("ax", 9), Err(MoveError::ParseIndex));
+		assert_eq!(MoveError::WrongBoard { active: 4 }.code(), "wrong_board");
+	}
+
+	#[test]
+	fn parse_notations() {
+		for sq in 0..81 {
+			let mv = Move::from_square(sq).unwrap();
+
+			assert_eq!(mv.to_string().parse(), Ok(mv));
+			assert_eq!(mv.to_string().to_lowercase().parse(), Ok(mv));
+			assert_eq!(format!("{sq:02}").parse(), Ok(mv));
+		}
+
+		assert_eq!("0,0".parse(), Ok(Move::new(0, 0)));
+		assert_eq!("4, 5".parse(), Ok(Move::new(4, 5)));
+		assert_eq!("8,6".parse(), Ok(Move::new(8, 6)));
+		assert_eq!("3,2".parse(), Ok(Move::new(3, 2)));
+
+		assert_eq!("9,0".parse::<Move>(), Err(MoveError::ParseCoord));
+		assert_eq!("-1,0".parse::<Move>(), Err(MoveError::ParseCoord));
+		assert_eq!("81".parse::<Move>(), Err(MoveError::ParseSquare));
+		assert_eq!("300".parse::<Move>(), Err(MoveError::ParseSquare));
+		assert_eq!("a0".parse::<Move>(), Err(MoveError::ParseIndex));
+		assert_eq!("".parse::<Move>(), Err(MoveError::ParseFormat));
+
+		assert_eq!("+4,4".parse::<Move>(), Err(MoveError::ParseCoord));
+		assert_eq!("4,+4".parse::<Move>(), Err(MoveError::ParseCoord));
+		assert_eq!("+40".parse::<Move>(), Err(MoveError::ParseFormat));
+
+		// A lone digit is a global square, except to `parse_move` where it's a cell of the active
+		// board
+		assert_eq!("5".parse(), Ok(Move::new(0, 5)));
+		assert_eq!("0".parse(), Ok(Move::new(0, 0)));
+		assert_eq!(parse_move("5", 9), Err(MoveError::ParseShorthand));
+		assert_eq!(parse_move("05", 4), Ok(Move::new(0, 5)));
+		assert_eq!(parse_move("0", 4), Err(MoveError::ParseIndex));
+		assert_eq!(parse_move("5", 4), Ok(Move::new(4, 4)));
+		assert_eq!(parse_move("B5", 9), Ok(Move::new(1, 4)));
+
+		assert_eq!(parse_numpad("7", 2), Ok(Move::new(2, 0)));
+		assert_eq!(parse_numpad("c3", 9), Ok(Move::new(2, 8)));
+		assert_eq!(parse_numpad("40", 9), Ok(Move::new(4, 4)));
+	}
+
+	#[test]
+	fn checked_constructors() {
+		for sq in 0..81 {
+			let mv = Move::from_square(sq).unwrap();
+
+			assert_eq!(Move::try_new(mv.game(), mv.index()), Ok(mv));
+			assert_eq!(Move::from_raw(mv.0), Some(mv));
+			assert_eq!(Move::from_parts(mv.sub_board(), mv.cell()), mv);
+		}
+
+		assert_eq!(Move::try_new(9, 0), Err(MoveError::OutOfRange));
+		assert_eq!(Move::try_new(0, 16), Err(MoveError::OutOfRange));
+		assert_eq!(Player::try_from(Slot::Empty), Err(MoveError::NotASide));
+		assert_eq!(Player::try_from(Slot::O), Ok(Player::O));
+		assert_eq!(Move::from_raw(0x09), None);
+		assert_eq!(Move::from_raw(0x90), None);
+		assert_eq!(SubBoard::new(9), None);
+		assert_eq!(Cell::try_from(8).map(Cell::get), Ok(8));
+		assert_eq!(Move::from_square(81), None);
+		assert_eq!(Squares::new(u128::MAX).count(), 81);
+
+		let mut mv = Move::new(0, 0);
+		mv.set_game(SubBoard::new(8).unwrap());
+		mv.set_idx(Cell::new(3).unwrap());
+		assert_eq!(mv, Move::new(8, 3));
+	}
+
+	#[test]
+	#[should_panic]
+	fn new_checks_range() {
+		// Used to be masked into a move in board 0 in release builds
+		Move::new(16, 0);
+	}
+}
@@ -0,0 +1,502 @@
+//! Small NNUE-style evaluator, an alternative to `score_game`
+//!
+//! One hidden layer over 199 binary inputs: the 81 X cells, the 81 O cells, three state bits
+//! (won, lost, tied) for each sub-board and a one-hot `active`. The hidden layer's inputs are kept
+//! in an `Accumulator` so a move only has to add or subtract the rows of the few inputs it changed.
+//!
+//! Weight files are little-endian binary:
+//! ```text
+//! b"UTNN"  u32 version  u32 inputs  u32 hidden
+//! f32 w1[inputs][hidden]  f32 b1[hidden]  f32 w2[hidden]  f32 b2
+//! ```
+
+use std::path::Path;
+
+use rand::{Rng, SeedableRng, rngs::SmallRng, seq::SliceRandom};
+
+use crate::{
+	bitboard::{
+		BitBoard,
+		consts::{O_MASK, X_MASK},
+	},
+	board::{Slot, State},
+	counting::search_with,
+	eval::Evaluator,
+	game::{Game, Undo},
+	moves::{Move, MoveError, generate_moves},
+};
+
+pub const INPUTS: usize = 199;
+pub const HIDDEN: usize = 32;
+
+const X_FEATS: usize = 0;
+const O_FEATS: usize = 81;
+const STATE_FEATS: usize = 162;
+const ACTIVE_FEATS: usize = 189;
+
+/// The network's output is in units of `score_game / SCALE`
+const SCALE: f32 = 400.0;
+
+const MAGIC: &[u8; 4] = b"UTNN";
+const VERSION: u32 = 1;
+
+/// Depth of the search used when generating training games
+const SELFPLAY_DEPTH: u8 = 3;
+
+/// Features of one sub-board packed into a u32, the X and O cells use the same bits as in
+/// `BitBoard` and bits 18..21 are the won/lost/tied state
+fn board_feats(brd: BitBoard) -> u32 {
+	let state = match brd.state() {
+		State::Undecided => 0,
+		State::Won => 1,
+		State::Lost => 2,
+		State::Tied => 4,
+	};
+
+	brd.0 & (X_MASK | O_MASK) | state << 18
+}
+
+/// Maps a bit of `board_feats` to its input index
+fn feat_index(bdx: usize, bit: u32) -> usize {
+	let bit = bit as usize;
+
+	match bit {
+		0..9 => X_FEATS + bdx * 9 + bit,
+		9..18 => O_FEATS + bdx * 9 + bit - 9,
+		_ => STATE_FEATS + bdx * 3 + bit - 18,
+	}
+}
+
+fn for_each_bit(mut bits: u32, mut f: impl FnMut(u32)) {
+	while bits != 0 {
+		f(bits.trailing_zeros());
+		bits &= bits - 1;
+	}
+}
+
+/// Every active input of `game`
+pub fn features(game: &Game) -> Vec<usize> {
+	let mut feats = vec![];
+
+	for (bdx, brd) in game.boards.iter().enumerate() {
+		for_each_bit(board_feats(*brd), |bit| feats.push(feat_index(bdx, bit)));
+	}
+
+	feats.push(ACTIVE_FEATS + game.active as usize);
+
+	feats
+}
+
+/// `1 / (1 + 10^-y)`, the same curve as `tuner::sigmoid` once the output is scaled
+fn sigmoid(y: f32) -> f32 {
+	1.0 / (1.0 + 10f32.powf(-y))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+	/// One row per input
+	w1: Vec<[f32; HIDDEN]>,
+	b1: [f32; HIDDEN],
+	w2: [f32; HIDDEN],
+	b2: f32,
+}
+
+impl Network {
+	/// Small random weights to start training from
+	pub fn random(rng: &mut SmallRng) -> Network {
+		let mut rand = || rng.random_range(-0.1..0.1);
+
+		Network {
+			w1: (0..INPUTS)
+				.map(|_| std::array::from_fn(|_| rand()))
+				.collect(),
+			b1: std::array::from_fn(|_| rand()),
+			w2: std::array::from_fn(|_| rand()),
+			b2: 0.0,
+		}
+	}
+
+	fn output(&self, acc: &Accumulator) -> f32 {
+		let mut out = self.b2;
+
+		for (w, v) in self.w2.iter().zip(acc.0) {
+			out += w * v.clamp(0.0, 1.0);
+		}
+
+		out
+	}
+
+	/// Score of `acc`'s position in the same units as `score_game`
+	pub fn evaluate(&self, acc: &Accumulator) -> i32 {
+		(self.output(acc) * SCALE) as i32
+	}
+
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut buf = Vec::with_capacity(16 + 4 * (INPUTS * HIDDEN + 2 * HIDDEN + 1));
+
+		buf.extend_from_slice(MAGIC);
+		buf.extend_from_slice(&VERSION.to_le_bytes());
+		buf.extend_from_slice(&(INPUTS as u32).to_le_bytes());
+		buf.extend_from_slice(&(HIDDEN as u32).to_le_bytes());
+
+		let floats = self.w1.iter().flatten().chain(&self.b1).chain(&self.w2);
+		for f in floats.chain([&self.b2]) {
+			buf.extend_from_slice(&f.to_le_bytes());
+		}
+
+		buf
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Network, &'static str> {
+		let word = |idx: usize| -> [u8; 4] { bytes[idx * 4..idx * 4 + 4].try_into().unwrap() };
+
+		if bytes.len() != 16 + 4 * (INPUTS * HIDDEN + 2 * HIDDEN + 1) {
+			return Err("weight file has the wrong size");
+		}
+
+		if &word(0) != MAGIC {
+			return Err("not a network weight file");
+		}
+
+		if u32::from_le_bytes(word(1)) != VERSION {
+			return Err("unsupported weight file version");
+		}
+
+		if u32::from_le_bytes(word(2)) as usize != INPUTS
+			|| u32::from_le_bytes(word(3)) as usize != HIDDEN
+		{
+			return Err("weight file has the wrong layer sizes");
+		}
+
+		let mut floats = (4..).map(|idx| f32::from_le_bytes(word(idx)));
+		let mut next = || floats.next().unwrap();
+
+		Ok(Network {
+			w1: (0..INPUTS)
+				.map(|_| std::array::from_fn(|_| next()))
+				.collect(),
+			b1: std::array::from_fn(|_| next()),
+			w2: std::array::from_fn(|_| next()),
+			b2: next(),
+		})
+	}
+
+	pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+		std::fs::write(path, self.to_bytes())
+	}
+
+	pub fn load(path: impl AsRef<Path>) -> Result<Network, &'static str> {
+		let bytes = std::fs::read(path).map_err(|_| "could not read weight file")?;
+
+		Self::from_bytes(&bytes)
+	}
+}
+
+impl Evaluator for Network {
+	type Cache = Accumulator;
+
+	fn score_game(&self, game: &Game, turn: Slot) -> i32 {
+		self.score_cached(&Accumulator::new(self, game), game, turn)
+	}
+
+	fn new_cache(&self, game: &Game) -> Accumulator {
+		Accumulator::new(self, game)
+	}
+
+	#[inline]
+	fn update_cache(&self, acc: &mut Accumulator, game: &Game, undo: &Undo) {
+		acc.update(self, game, undo);
+	}
+
+	fn score_cached(&self, acc: &Accumulator, game: &Game, _turn: Slot) -> i32 {
+		// Keep finished games on the same scale as `score_game` so the search still goes for (and
+		// avoids) won games
+		match game.state() {
+			State::Won => 1_000_000,
+			State::Lost => -1_000_000,
+			State::Tied => 0,
+			State::Undecided => self.evaluate(acc),
+		}
+	}
+}
+
+/// The hidden layer before activation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accumulator([f32; HIDDEN]);
+
+impl Accumulator {
+	pub fn new(net: &Network, game: &Game) -> Accumulator {
+		let mut acc = Accumulator(net.b1);
+
+		for feat in features(game) {
+			acc.add(net, feat);
+		}
+
+		acc
+	}
+
+	/// Brings the accumulator up to date with `game`, which just had the move `undo` was returned
+	/// for made. Only the board that was played in can change, other than `active`
+	pub fn update(&mut self, net: &Network, game: &Game, undo: &Undo) {
+		let bdx = undo.mv.game() as usize;
+		let old = board_feats(undo.board);
+		let new = board_feats(game.boards[bdx]);
+
+		for_each_bit(old & !new, |bit| self.sub(net, feat_index(bdx, bit)));
+		for_each_bit(new & !old, |bit| self.add(net, feat_index(bdx, bit)));
+
+		if undo.active != game.active {
+			self.sub(net, ACTIVE_FEATS + undo.active as usize);
+			self.add(net, ACTIVE_FEATS + game.active as usize);
+		}
+	}
+
+	fn add(&mut self, net: &Network, feat: usize) {
+		for (v, w) in self.0.iter_mut().zip(net.w1[feat]) {
+			*v += w;
+		}
+	}
+
+	fn sub(&mut self, net: &Network, feat: usize) {
+		for (v, w) in self.0.iter_mut().zip(net.w1[feat]) {
+			*v -= w;
+		}
+	}
+}
+
+/// A `Game` along with its accumulator, which is updated incrementally on every move
+#[derive(Debug, Clone)]
+pub struct NnGame {
+	pub game: Game,
+	pub acc: Accumulator,
+}
+
+impl NnGame {
+	pub fn new(net: &Network, game: Game) -> NnGame {
+		NnGame {
+			acc: Accumulator::new(net, &game),
+			game,
+		}
+	}
+
+	pub fn make_move(&mut self, net: &Network, mv: Move) -> Result<(), MoveError> {
+		let undo = self.game.make_move(mv)?;
+
+		self.acc.update(net, &self.game, &undo);
+
+		Ok(())
+	}
+
+	pub fn evaluate(&self, net: &Network) -> i32 {
+		net.score_cached(&self.acc, &self.game, Slot::Empty)
+	}
+}
+
+/// Plays `games` games of the network against itself and labels every position with the result
+/// for X (1.0 won, 0.0 lost, 0.5 tied). The first `random_plies` moves of each game are random.
+pub fn selfplay(
+	net: &Network,
+	games: usize,
+	random_plies: u8,
+	rng: &mut SmallRng,
+) -> Vec<(Game, f32)> {
+	let mut samples = vec![];
+
+	for _ in 0..games {
+		let mut game = Game::new();
+		let start = samples.len();
+
+		while game.state() == State::Undecided {
+			let lgms = generate_moves(&game);
+
+			if lgms.is_empty() {
+				break;
+			}
+
+			let mv = if samples.len() - start < random_plies as usize {
+				lgms[rng.random_range(0..lgms.len())]
+			} else {
+				search_with::<SELFPLAY_DEPTH>(&game, net).1.unwrap()
+			};
+
+			game.make_move(mv).unwrap();
+			samples.push((game.clone(), 0.0));
+		}
+
+		let result = match game.state() {
+			State::Won => 1.0,
+			State::Lost => 0.0,
+			_ => 0.5,
+		};
+
+		for (_, label) in &mut samples[start..] {
+			*label = result;
+		}
+	}
+
+	samples
+}
+
+/// Runs `epochs` passes of SGD over `samples` minimizing the squared error between the predicted
+/// and actual results, returns the mean loss of the last epoch
+pub fn train(
+	net: &mut Network,
+	samples: &[(Game, f32)],
+	epochs: usize,
+	lr: f32,
+	rng: &mut SmallRng,
+) -> f32 {
+	let mut order: Vec<usize> = (0..samples.len()).collect();
+	let mut loss = 0.0;
+
+	for _ in 0..epochs {
+		order.shuffle(rng);
+		loss = 0.0;
+
+		for &idx in &order {
+			let (game, target) = &samples[idx];
+			let feats = features(game);
+			let acc = Accumulator::new(net, game);
+
+			let pred = sigmoid(net.output(&acc));
+			loss += (pred - target).powi(2);
+
+			// d(loss)/d(output)
+			let grad = 2.0 * (pred - target) * std::f32::consts::LN_10 * pred * (1.0 - pred);
+
+			let mut hidden_grad = [0.0; HIDDEN];
+			for ((hg, w), v) in hidden_grad.iter_mut().zip(&mut net.w2).zip(acc.0) {
+				if v > 0.0 && v < 1.0 {
+					*hg = grad * *w;
+				}
+
+				*w -= lr * grad * v.clamp(0.0, 1.0);
+			}
+			net.b2 -= lr * grad;
+
+			for (b, g) in net.b1.iter_mut().zip(hidden_grad) {
+				*b -= lr * g;
+			}
+
+			for feat in feats {
+				for (w, g) in net.w1[feat].iter_mut().zip(hidden_grad) {
+					*w -= lr * g;
+				}
+			}
+		}
+
+		loss /= samples.len().max(1) as f32;
+	}
+
+	loss
+}
+
+/// Self-play then train, `generations` times over, starting from a random network
+pub fn train_from_scratch(
+	generations: usize,
+	games: usize,
+	epochs: usize,
+	seed: u64,
+	mut on_generation: impl FnMut(usize, &Network, f32),
+) -> Network {
+	let mut rng = SmallRng::seed_from_u64(seed);
+	let mut net = Network::random(&mut rng);
+
+	for gen_idx in 0..generations {
+		let samples = selfplay(&net, games, 6, &mut rng);
+		let loss = train(&mut net, &samples, epochs, 0.01, &mut rng);
+
+		on_generation(gen_idx, &net, loss);
+	}
+
+	net
+}
+
+#[cfg(test)]
+mod test {
+	use rand::{SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+
+	use super::{Accumulator, Network, NnGame, selfplay, train};
+	use crate::{
+		board::Slot,
+		counting::search_with,
+		eval::Evaluator,
+		game::{Game, Undo},
+		moves::legal_moves,
+	};
+
+	/// Scores every leaf with a freshly built accumulator
+	struct Fresh<'a>(&'a Network);
+
+	impl Evaluator for Fresh<'_> {
+		type Cache = ();
+
+		fn score_game(&self, game: &Game, turn: Slot) -> i32 {
+			self.0.score_game(game, turn)
+		}
+
+		fn new_cache(&self, _game: &Game) {}
+
+		fn update_cache(&self, _cache: &mut (), _game: &Game, _undo: &Undo) {}
+
+		fn score_cached(&self, _cache: &(), game: &Game, turn: Slot) -> i32 {
+			self.0.score_game(game, turn)
+		}
+	}
+
+	#[test]
+	fn incremental_matches_refresh() {
+		let mut rng = SmallRng::seed_from_u64(1);
+		let net = Network::random(&mut rng);
+		let mut nn = NnGame::new(&net, Game::new());
+
+		for _ in 0..40 {
+			let lgms = legal_moves(&nn.game);
+			let Some(mv) = lgms.choose(&mut rng) else {
+				break;
+			};
+
+			nn.make_move(&net, *mv).unwrap();
+
+			let fresh = Accumulator::new(&net, &nn.game);
+			for (a, b) in nn.acc.0.iter().zip(fresh.0) {
+				assert!((a - b).abs() < 1e-4);
+			}
+		}
+	}
+
+	#[test]
+	fn search_uses_accumulator() {
+		let net = Network::random(&mut SmallRng::seed_from_u64(4));
+
+		for times in [0, 7, 20, 35] {
+			let game = Game::random(times);
+
+			assert_eq!(
+				search_with::<3>(&game, &net).0,
+				search_with::<3>(&game, &Fresh(&net)).0
+			);
+		}
+	}
+
+	#[test]
+	fn bytes_round_trip() {
+		let net = Network::random(&mut SmallRng::seed_from_u64(2));
+		let bytes = net.to_bytes();
+
+		assert_eq!(Network::from_bytes(&bytes), Ok(net));
+		assert!(Network::from_bytes(&bytes[1..]).is_err());
+	}
+
+	#[test]
+	fn training_lowers_loss() {
+		let mut rng = SmallRng::seed_from_u64(3);
+		let mut net = Network::random(&mut rng);
+		let samples = selfplay(&net, 2, 60, &mut rng);
+
+		let first = train(&mut net, &samples, 1, 0.01, &mut rng);
+		let last = train(&mut net, &samples, 20, 0.01, &mut rng);
+
+		assert!(last < first);
+	}
+}
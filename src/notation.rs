@@ -0,0 +1,284 @@
+//! A one line text format for positions, in the spirit of chess FEN
+//!
+//! A position is four fields separated by spaces, like `xx.oo..../xxx....../xxx....../` then six
+//! empty boards and ` -xx------ x a`:
+//! - the 9 boards separated by `/`, each as its 9 cells from the top left (`x`, `o` or `.`)
+//! - the state of every board, `x` or `o` if that side won it, `t` if tied and `-` if undecided
+//! - the side to move, `x` or `o`
+//! - the active board as a letter, or `-` when any board can be played in
+
+use std::fmt::Display;
+
+use crate::{
+	bitboard::BitBoard,
+	board::{Player, Slot, State},
+	game::Game,
+	validate::ValidationError,
+};
+
+/// Why a string isn't a valid position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotationError {
+	/// There have to be exactly 4 fields
+	Fields,
+	/// There have to be exactly 9 boards
+	Boards,
+	/// Every board has to be 9 of `x`, `o` and `.`
+	Cells { board: u8 },
+	/// The states have to be 9 of `x`, `o`, `t` and `-`
+	States,
+	/// The side to move has to be `x` or `o`
+	Side,
+	/// The active board has to be a letter from a to i or `-`
+	Active,
+	/// Well formed, but not a position that can come up in a game. The first thing
+	/// `Game::validate` found wrong with it
+	Invalid(ValidationError),
+}
+
+impl NotationError {
+	pub const fn message(&self) -> &'static str {
+		match self {
+			NotationError::Fields => "a position is 4 fields: boards, states, side to move, active",
+			NotationError::Boards => "there must be 9 boards separated by /",
+			NotationError::Cells { .. } => "a board must be 9 of x, o and .",
+			NotationError::States => "the states must be 9 of x, o, t and -",
+			NotationError::Side => "side to move must be x or o",
+			NotationError::Active => "active board must be within a to i or -",
+			NotationError::Invalid(_) => "not a position that can come up in a game",
+		}
+	}
+}
+
+impl Display for NotationError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			NotationError::Cells { board } => {
+				write!(f, "{} ({})", self.message(), (board + b'a') as char)
+			}
+			NotationError::Invalid(err) => write!(f, "{}: {err}", self.message()),
+			_ => f.write_str(self.message()),
+		}
+	}
+}
+
+impl std::error::Error for NotationError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			NotationError::Invalid(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl Game {
+	pub fn to_notation(&self) -> String {
+		let mut out = String::with_capacity(105);
+
+		for (bdx, brd) in self.boards.iter().enumerate() {
+			if bdx != 0 {
+				out.push('/');
+			}
+
+			out.extend(brd.to_arr().map(|slot| match slot {
+				Slot::X => 'x',
+				Slot::O => 'o',
+				_ => '.',
+			}));
+		}
+
+		out.push(' ');
+		out.extend(self.boards.map(|brd| match brd.state() {
+			State::Won => 'x',
+			State::Lost => 'o',
+			State::Tied => 't',
+			State::Undecided => '-',
+		}));
+
+		out.push(' ');
+		out.push(match self.to_move() {
+			Player::X => 'x',
+			Player::O => 'o',
+		});
+
+		out.push(' ');
+		out.push(if self.active == 9 {
+			'-'
+		} else {
+			(self.active + b'a') as char
+		});
+
+		out
+	}
+
+	/// Reads a position written by `to_notation`, the letters can be in either case. The states
+	/// of the boards have to be the ones their cells give and the state of the whole game is
+	/// worked out from them
+	pub fn from_notation(src: &str) -> Result<Game, NotationError> {
+		let src = src.to_ascii_lowercase();
+		let fields: Vec<&str> = src.split_whitespace().collect();
+
+		let [boards, states, side, active] = fields[..] else {
+			return Err(NotationError::Fields);
+		};
+
+		let boards: Vec<&str> = boards.split('/').collect();
+		if boards.len() != 9 {
+			return Err(NotationError::Boards);
+		}
+
+		let states = states.as_bytes();
+		if states.len() != 9 {
+			return Err(NotationError::States);
+		}
+
+		let mut brds = [BitBoard::new(); 9];
+
+		for (bdx, cells) in boards.into_iter().enumerate() {
+			let err = NotationError::Cells { board: bdx as u8 };
+			let cells = cells.as_bytes();
+
+			if cells.len() != 9 {
+				return Err(err);
+			}
+
+			let mut slots = [Slot::Empty; 9];
+			for (slot, cell) in slots.iter_mut().zip(cells) {
+				*slot = match cell {
+					b'x' => Slot::X,
+					b'o' => Slot::O,
+					b'.' => Slot::Empty,
+					_ => return Err(err),
+				};
+			}
+
+			brds[bdx] = BitBoard::new_with(slots);
+			brds[bdx].set_state(match states[bdx] {
+				b'x' => State::Won,
+				b'o' => State::Lost,
+				b't' => State::Tied,
+				b'-' => State::Undecided,
+				_ => return Err(NotationError::States),
+			});
+		}
+
+		let side = match side {
+			"x" => Player::X,
+			"o" => Player::O,
+			_ => return Err(NotationError::Side),
+		};
+
+		let active = match active.as_bytes() {
+			b"-" => 9,
+			&[c @ b'a'..=b'i'] => c - b'a',
+			_ => return Err(NotationError::Active),
+		};
+
+		Game::from_boards(brds, side, active)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::NotationError;
+	use crate::{board::State, game::Game, moves::Move, validate::ValidationError};
+
+	const EMPTY: &str = "........./........./........./........./........./........./........./\
+		........./......... --------- x -";
+
+	#[test]
+	fn round_trip() {
+		assert_eq!(Game::new().to_notation(), EMPTY);
+		assert_eq!(Game::from_notation(EMPTY), Ok(Game::new()));
+
+		for times in 0..70 {
+			let game = Game::random(times);
+			let notation = game.to_notation();
+
+			assert_eq!(Game::from_notation(&notation), Ok(game));
+			assert_eq!(
+				Game::from_notation(&notation.to_uppercase())
+					.unwrap()
+					.to_notation(),
+				notation
+			);
+		}
+	}
+
+	#[test]
+	fn reads_positions() {
+		let game = Game::from_notation(
+			"xxx....../xxx....../xx.oo..../..o....o./o......../....o..../..o....../\
+			o......../......... xx------- x c",
+		)
+		.unwrap();
+
+		assert_eq!(game.boards[0].state(), State::Won);
+		assert_eq!(game.state(), State::Undecided);
+
+		let won = game.sim_move(Move::new(2, 2)).unwrap();
+		assert_eq!(won.state(), State::Won);
+		assert_eq!(
+			won.to_notation(),
+			"xxx....../xxx....../xxxoo..../..o....o./o......../....o..../..o....../\
+			o......../......... xxx------ o -"
+		);
+		assert_eq!(Game::from_notation(&won.to_notation()), Ok(won));
+
+		// Well formed, but O can't have only played twice to X's eight
+		assert_eq!(
+			Game::from_notation(
+				"xxx....../xxx....../xx.oo..../........./........./........./........./\
+				........./......... xx------- x c",
+			),
+			Err(NotationError::Invalid(ValidationError::MoveCounts {
+				x: 8,
+				o: 2
+			}))
+		);
+	}
+
+	#[test]
+	fn rejects_invalid() {
+		let err = |src: &str| Game::from_notation(src).unwrap_err();
+
+		assert_eq!(
+			err("........./......... --------- x -"),
+			NotationError::Boards
+		);
+		assert_eq!(err(&EMPTY.replace(" x -", " x")), NotationError::Fields);
+		assert_eq!(
+			err(&EMPTY.replacen(".........", "....z....", 1)),
+			NotationError::Cells { board: 0 }
+		);
+		assert_eq!(
+			err(&EMPTY.replacen(".........", "xxx......", 1)),
+			NotationError::Invalid(ValidationError::BoardState {
+				board: 0,
+				found: State::Undecided,
+				expected: State::Won,
+			})
+		);
+		assert_eq!(
+			err(&EMPTY.replace("---------", "-t-------")),
+			NotationError::Invalid(ValidationError::BoardState {
+				board: 1,
+				found: State::Tied,
+				expected: State::Undecided,
+			})
+		);
+		assert_eq!(
+			err(&EMPTY.replace("---------", "--?------")),
+			NotationError::States
+		);
+		assert_eq!(err(&EMPTY.replace(" x -", " - -")), NotationError::Side);
+		assert_eq!(err(&EMPTY.replace(" x -", " x j")), NotationError::Active);
+		assert_eq!(
+			err(&EMPTY
+				.replacen(".........", "xxx......", 1)
+				.replace("---------", "x--------")
+				.replace(" x -", " x a")),
+			NotationError::Invalid(ValidationError::ActiveDecided { active: 0 })
+		);
+	}
+}
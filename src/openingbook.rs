@@ -1,9 +1,8 @@
 use crate::{
 	bitboard::BitBoard,
-	board::Slot,
 	counting::alpha_beta,
 	game::Game,
-	moves::{Move, legal_moves},
+	moves::{Move, generate_moves},
 };
 
 // Stores a vec of game -> refutation move
@@ -20,22 +19,23 @@ impl OpeningBook {
 		let game = Game::new();
 
 		// Generate refutations for the second move
-		for mv in legal_moves(&game) {
-			let sub1_game = game.sim_move(mv, Slot::O).unwrap();
-			let (_scr, refutation) = alpha_beta(&sub1_game);
+		for mv in generate_moves(&game) {
+			let sub1_game = game.sim_move(mv).unwrap();
+			// Early in the game, so there's always a legal move
+			let refutation = alpha_beta(&sub1_game).1.unwrap();
 
 			inner.push((sub1_game.clone(), refutation));
 
-			let sub2_game = sub1_game.sim_move(refutation, Slot::X).unwrap();
+			let sub2_game = sub1_game.sim_move(refutation).unwrap();
 			let thrd_inner = std::thread::scope(|s| {
 				let mut handles = vec![];
 
 				// Generate refutations for the fourth move
-				for lg_mv in legal_moves(&sub2_game.clone()) {
+				for lg_mv in generate_moves(&sub2_game.clone()) {
 					let sub2_game = sub2_game.clone();
 					handles.push(s.spawn(move || {
-						let sub3_game = sub2_game.sim_move(lg_mv, Slot::O).unwrap();
-						let (_scr, refutation) = alpha_beta(&sub3_game);
+						let sub3_game = sub2_game.sim_move(lg_mv).unwrap();
+						let refutation = alpha_beta(&sub3_game).1.unwrap();
 
 						(sub3_game, refutation)
 					}));
@@ -0,0 +1,333 @@
+//! Alternative `Game` layout with whole-game bitboards
+//!
+//! Every cell of the game is one bit of an 81-bit plane per side, at `Move::square`, and the
+//! decided boards are 9-bit masks, so questions about the whole game (which squares are legal,
+//! which boards are still open) become a handful of `u128` operations instead of a loop over
+//! nine `BitBoard`s. Meant for benchmarking against `Game`, which it converts to and from exactly.
+//! Per-board checks go through the tables in `lut`.
+
+use crate::{
+	bitboard::BitBoard,
+	board::{Player, State},
+	game::Game,
+	lut::{winnable, won},
+	moves::{Move, MoveError, MoveList, Squares},
+};
+
+/// Every square of the game
+pub const ALL: u128 = (1 << 81) - 1;
+/// The squares of board 0, shift by `9 * board` for the others
+const BOARD: u128 = 0x1FF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
+pub struct PackedGame {
+	/// Squares taken by X, bit `Move::square`
+	pub x: u128,
+	/// Squares taken by O, bit `Move::square`
+	pub o: u128,
+	/// Boards won by X, bit n is board n
+	pub won_x: u16,
+	/// Boards won by O
+	pub won_o: u16,
+	/// Boards neither side can win anymore
+	pub tied: u16,
+	/// Indicates active board, 0-8 is the idx, 9 means any board is free
+	pub active: u8,
+	/// The side whose turn it is
+	pub to_move: Player,
+	/// State of the whole game, kept up to date by `make_move` like `Game` keeps its state bits
+	pub state: State,
+}
+
+/// A `BitBoard` with X on `x` and O on `o`, every other cell empty unless it's in `disabled`
+#[inline]
+const fn to_bitboard(x: u16, o: u16, disabled: u16) -> BitBoard {
+	let x = x as u32;
+	let o = o as u32;
+	let empty = !(x | o | disabled as u32) & 0x1FF;
+
+	BitBoard(x | (o << 9) | (empty << 18))
+}
+
+/// Spreads a 9-bit mask of boards to every square of those boards
+#[inline]
+const fn board_squares(boards: u16) -> u128 {
+	let mut squares = 0;
+	let mut bdx = 0;
+
+	while bdx < 9 {
+		if boards & (1 << bdx) != 0 {
+			squares |= BOARD << (9 * bdx);
+		}
+
+		bdx += 1;
+	}
+
+	squares
+}
+
+impl PackedGame {
+	pub const fn new() -> Self {
+		PackedGame {
+			x: 0,
+			o: 0,
+			won_x: 0,
+			won_o: 0,
+			tied: 0,
+			active: 9,
+			to_move: Player::X,
+			state: State::Undecided,
+		}
+	}
+
+	/// The X cells of board `bdx` as 9 bits
+	#[inline]
+	pub const fn board_x(&self, bdx: u8) -> u16 {
+		((self.x >> (9 * bdx)) & BOARD) as u16
+	}
+
+	/// The O cells of board `bdx` as 9 bits
+	#[inline]
+	pub const fn board_o(&self, bdx: u8) -> u16 {
+		((self.o >> (9 * bdx)) & BOARD) as u16
+	}
+
+	#[inline]
+	pub const fn decided(&self) -> u16 {
+		self.won_x | self.won_o | self.tied
+	}
+
+	#[inline]
+	pub const fn board_state(&self, bdx: u8) -> State {
+		if self.won_x & (1 << bdx) != 0 {
+			State::Won
+		} else if self.won_o & (1 << bdx) != 0 {
+			State::Lost
+		} else if self.tied & (1 << bdx) != 0 {
+			State::Tied
+		} else {
+			State::Undecided
+		}
+	}
+
+	/// Same as `Game::shrink`, tied boards are disabled cells
+	#[inline]
+	pub const fn shrink(&self) -> BitBoard {
+		to_bitboard(self.won_x, self.won_o, self.tied)
+	}
+
+	#[inline]
+	pub const fn state(&self) -> State {
+		self.state
+	}
+
+	/// The state the decided boards give the whole game, see `Game::make_move`
+	fn meta_state(&self) -> State {
+		if won(self.won_x) {
+			State::Won
+		} else if won(self.won_o) {
+			State::Lost
+		} else if !winnable(self.won_x, self.won_o, !self.decided()) {
+			State::Tied
+		} else {
+			State::Undecided
+		}
+	}
+
+	/// Every legal square as a bit, same as `Game::legal_mask`
+	#[inline]
+	pub fn legal_mask(&self) -> u128 {
+		if self.state() != State::Undecided {
+			return 0;
+		}
+
+		let open = if self.active == 9 {
+			ALL
+		} else {
+			BOARD << (9 * self.active)
+		};
+
+		open & !(self.x | self.o) & !board_squares(self.decided())
+	}
+
+	#[inline]
+	pub fn legal_squares(&self) -> Squares {
+		Squares(self.legal_mask())
+	}
+
+	/// Same moves in the same order as `generate_moves` on the equivalent `Game`
+	pub fn legal_moves(&self) -> MoveList {
+		let mut list = MoveList::new();
+
+		for mv in self.legal_squares() {
+			list.push(mv);
+		}
+
+		list
+	}
+
+	/// Same checks, in the same order, as `moves::is_legal`
+	pub fn is_legal(&self, mv: Move) -> Result<(), MoveError> {
+		if self.state() != State::Undecided {
+			return Err(MoveError::GameOver);
+		}
+
+		if self.decided() & (1 << mv.game()) != 0 {
+			return Err(MoveError::BoardFinished);
+		}
+
+		if (self.x | self.o) & (1 << mv.square()) != 0 {
+			return Err(MoveError::SquareOccupied);
+		}
+
+		if self.active != mv.game() && self.active != 9 {
+			return Err(MoveError::WrongBoard {
+				active: self.active,
+			});
+		}
+
+		Ok(())
+	}
+
+	pub fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+		self.is_legal(mv)?;
+
+		let side = self.to_move;
+
+		let bdx = mv.game();
+		let bit = 1 << mv.square();
+
+		match side {
+			Player::X => self.x |= bit,
+			Player::O => self.o |= bit,
+		}
+
+		let (bx, bo) = (self.board_x(bdx), self.board_o(bdx));
+		let decided = self.decided();
+
+		if won(bx) {
+			self.won_x |= 1 << bdx;
+		} else if won(bo) {
+			self.won_o |= 1 << bdx;
+		} else if !winnable(bx, bo, !(bx | bo)) {
+			self.tied |= 1 << bdx;
+		}
+
+		// The game can only be decided by deciding a board
+		if self.decided() != decided {
+			self.state = self.meta_state();
+		}
+
+		self.active = if self.decided() & (1 << mv.index()) != 0 {
+			9
+		} else {
+			mv.index()
+		};
+		self.to_move = side.flip();
+
+		Ok(())
+	}
+}
+
+impl Default for PackedGame {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl From<&Game> for PackedGame {
+	fn from(game: &Game) -> Self {
+		let mut packed = PackedGame {
+			active: game.active,
+			to_move: game.to_move(),
+			state: game.state(),
+			..PackedGame::new()
+		};
+
+		for (bdx, brd) in game.boards.iter().enumerate() {
+			let shift = 9 * bdx;
+
+			packed.x |= ((brd.0 & 0x1FF) as u128) << shift;
+			packed.o |= (((brd.0 >> 9) & 0x1FF) as u128) << shift;
+
+			match brd.state() {
+				State::Won => packed.won_x |= 1 << bdx,
+				State::Lost => packed.won_o |= 1 << bdx,
+				State::Tied => packed.tied |= 1 << bdx,
+				State::Undecided => {}
+			}
+		}
+
+		packed
+	}
+}
+
+impl From<&PackedGame> for Game {
+	fn from(packed: &PackedGame) -> Self {
+		let mut game = Game::new();
+
+		for bdx in 0..9 {
+			let brd = &mut game.boards[bdx as usize];
+
+			*brd = to_bitboard(packed.board_x(bdx), packed.board_o(bdx), 0);
+			brd.set_state(packed.board_state(bdx));
+		}
+
+		game.active = packed.active;
+		game.set_to_move(packed.to_move);
+		game.set_state(packed.state);
+
+		game
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::PackedGame;
+	use crate::{board::State, game::Game, moves::generate_moves};
+
+	#[test]
+	fn round_trip() {
+		for times in 0..70 {
+			let game = Game::random(times);
+			let packed = PackedGame::from(&game);
+
+			assert_eq!(Game::from(&packed), game);
+			assert_eq!(packed.state(), game.state());
+		}
+
+		let game = Game::_test();
+		assert_eq!(Game::from(&PackedGame::from(&game)), game);
+
+		// The state is kept even when it isn't the one the boards give
+		let mut game = Game::new();
+		game.set_state(State::Tied);
+		assert_eq!(Game::from(&PackedGame::from(&game)), game);
+	}
+
+	#[test]
+	fn plays_like_game() {
+		for seed in 0..20 {
+			let mut game = Game::new();
+			let mut packed = PackedGame::new();
+
+			for ply in 0.. {
+				let lgms = generate_moves(&game);
+
+				assert_eq!(packed.legal_moves(), lgms);
+				assert_eq!(packed.legal_mask(), game.legal_mask());
+
+				if lgms.is_empty() || game.state() != State::Undecided {
+					break;
+				}
+
+				let mv = lgms[(seed * 31 + ply * 7) % lgms.len()];
+				game.make_move(mv).unwrap();
+				packed.make_move(mv).unwrap();
+
+				assert_eq!(packed, PackedGame::from(&game));
+				assert_eq!(packed.state(), game.state());
+			}
+		}
+	}
+}
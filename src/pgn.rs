@@ -0,0 +1,783 @@
+//! A text format for whole games, modelled on chess PGN
+//!
+//! ```text
+//! [X "ultimengine"]
+//! [O "human"]
+//! [Result "1-0"]
+//!
+//! 1. E5 E1 { [%eval 12] the usual reply } 2. A5 (2. A1 A2) 2... E9 1-0
+//! ```
+//!
+//! Tags come first, then the moves in `Move` notation with optional move numbers (X moves first, so
+//! each number starts with X's move) and `--` for a turn that was passed. A game that doesn't
+//! start from the empty board has its first position in a `Position` tag, in the notation of
+//! `Game::to_notation`, and is numbered from 1 there. A comment in `{}` belongs to the move before
+//! it and can hold an evaluation as `[%eval n]`, a `\` in one makes the next character part of the
+//! text. A variation in `()` is a line played instead of the move before it and can have
+//! variations of its own. The game ends with its result: `1-0` if X won, `0-1` if O won,
+//! `1/2-1/2` for a tie and `*` if it isn't over, which has to be how the main line ends.
+
+use std::{
+	fmt::{Display, Write},
+	str::FromStr,
+};
+
+use crate::{
+	board::{Player, State},
+	game::Game,
+	moves::{Move, MoveError},
+	notation::NotationError,
+	record::{Entry, GameRecord},
+};
+
+/// The tag holding the position a game starts from, when it isn't `Game::new`
+pub const POSITION_TAG: &str = "Position";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Outcome {
+	XWon,
+	OWon,
+	Tied,
+	#[default]
+	Unfinished,
+}
+
+impl Outcome {
+	pub const fn from_state(state: State) -> Outcome {
+		match state {
+			State::Won => Outcome::XWon,
+			State::Lost => Outcome::OWon,
+			State::Tied => Outcome::Tied,
+			State::Undecided => Outcome::Unfinished,
+		}
+	}
+
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Outcome::XWon => "1-0",
+			Outcome::OWon => "0-1",
+			Outcome::Tied => "1/2-1/2",
+			Outcome::Unfinished => "*",
+		}
+	}
+
+	fn parse(word: &str) -> Option<Outcome> {
+		[
+			Outcome::XWon,
+			Outcome::OWon,
+			Outcome::Tied,
+			Outcome::Unfinished,
+		]
+		.into_iter()
+		.find(|o| o.as_str() == word)
+	}
+}
+
+impl Display for Outcome {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+/// A move of the game with what was said about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnMove {
+	/// `None` for a passed turn
+	pub mv: Option<Move>,
+	/// Anything but surrounding whitespace, which isn't kept
+	pub comment: Option<String>,
+	pub eval: Option<i32>,
+	/// Lines played instead of this move
+	pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+	pub fn new(mv: Move) -> Self {
+		PgnMove {
+			mv: Some(mv),
+			..PgnMove::pass()
+		}
+	}
+
+	pub fn pass() -> Self {
+		PgnMove {
+			mv: None,
+			comment: None,
+			eval: None,
+			variations: vec![],
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Pgn {
+	/// Written in this order, like `("X", "ultimengine")`
+	pub tags: Vec<(String, String)>,
+	/// Played from `start`
+	pub moves: Vec<PgnMove>,
+	pub result: Outcome,
+}
+
+/// Why a game record couldn't be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PgnError {
+	/// A tag isn't `[Name "value"]`
+	Tag,
+	/// A `{` or `(` is never closed, or a `}` or `)` closes nothing
+	Unbalanced,
+	/// A comment or variation comes before any move it could belong to
+	Dangling,
+	/// Something other than whitespace follows the result, or a variation has a result
+	Result,
+	/// The result isn't the one the final position of the main line gives
+	WrongResult { found: Outcome, expected: Outcome },
+	/// The move at `ply` (counted from the start of the game) couldn't be read or isn't legal
+	Move { ply: usize, err: MoveError },
+	/// The `Position` tag isn't a valid position
+	Position(NotationError),
+}
+
+impl Display for PgnError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			PgnError::Tag => f.write_str("tags look like [Name \"value\"]"),
+			PgnError::Unbalanced => f.write_str("unbalanced { } or ( )"),
+			PgnError::Dangling => f.write_str("comments and variations have to follow a move"),
+			PgnError::Result => f.write_str("the result has to end the game"),
+			PgnError::WrongResult { found, expected } => {
+				write!(f, "the result is {found} but the game ends {expected}")
+			}
+			PgnError::Move { ply, err } => write!(f, "move {}: {err}", ply + 1),
+			PgnError::Position(err) => write!(f, "{POSITION_TAG} tag: {err}"),
+		}
+	}
+}
+
+impl std::error::Error for PgnError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			PgnError::Position(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl Pgn {
+	/// A record of `moves` without tags, comments or a result
+	pub fn new(moves: impl IntoIterator<Item = Move>) -> Self {
+		Pgn {
+			moves: moves.into_iter().map(PgnMove::new).collect(),
+			..Pgn::default()
+		}
+	}
+
+	pub fn tag(&self, name: &str) -> Option<&str> {
+		self.tags
+			.iter()
+			.find(|(n, _)| n == name)
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// Replaces the tag if it's already there, otherwise adds it at the end
+	pub fn set_tag(&mut self, name: &str, value: impl Into<String>) {
+		let value = value.into();
+
+		match self.tags.iter_mut().find(|(n, _)| n == name) {
+			Some((_, v)) => *v = value,
+			None => self.tags.push((name.to_string(), value)),
+		}
+	}
+
+	/// Everything played in `record` from where it started, with the result it's at
+	pub fn from_record(record: &GameRecord) -> Self {
+		let mut pgn = Pgn {
+			moves: record
+				.entries()
+				.map(|entry| match entry {
+					Entry::Move(undo) => PgnMove::new(undo.mv),
+					Entry::Pass(_) => PgnMove::pass(),
+				})
+				.collect(),
+			result: Outcome::from_state(record.game().state()),
+			..Pgn::default()
+		};
+		pgn.set_start(&record.start());
+
+		pgn
+	}
+
+	/// The position the game starts from, `Game::new` without a `Position` tag
+	pub fn start(&self) -> Result<Game, PgnError> {
+		self.tag(POSITION_TAG).map_or_else(
+			|| Ok(Game::new()),
+			|pos| Game::from_notation(pos).map_err(PgnError::Position),
+		)
+	}
+
+	/// Sets the `Position` tag, or takes it out for `Game::new`
+	pub fn set_start(&mut self, game: &Game) {
+		if *game == Game::new() {
+			self.tags.retain(|(name, _)| name != POSITION_TAG);
+		} else {
+			self.set_tag(POSITION_TAG, game.to_notation());
+		}
+	}
+
+	/// The position at the end of the main line
+	pub fn game(&self) -> Result<Game, PgnError> {
+		let start = self.start()?;
+
+		check_line(&start, &self.moves, first_ply(&start))
+	}
+
+	/// Reads a game and checks that every move of it, variations included, is legal and that the
+	/// result is the one the main line ends in. A missing result is taken from the main line
+	pub fn parse(src: &str) -> Result<Pgn, PgnError> {
+		let mut parser = Parser { src, pos: 0 };
+		let mut tags = vec![];
+
+		while parser.skip_ws() == Some('[') {
+			tags.push(parser.tag()?);
+		}
+
+		let mut pgn = Pgn {
+			tags,
+			..Pgn::default()
+		};
+		let start = pgn.start()?;
+
+		let (moves, result) = parser.line(first_ply(&start), false)?;
+		pgn.moves = moves;
+
+		let expected = Outcome::from_state(pgn.game()?.state());
+		if let Some(found) = result
+			&& found != expected
+		{
+			return Err(PgnError::WrongResult { found, expected });
+		}
+		pgn.result = expected;
+
+		Ok(pgn)
+	}
+}
+
+impl FromStr for Pgn {
+	type Err = PgnError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Pgn::parse(s)
+	}
+}
+
+/// The ply of the first move from `start`, so that X's moves stay on even plies
+fn first_ply(start: &Game) -> usize {
+	match start.to_move() {
+		Player::X => 0,
+		Player::O => 1,
+	}
+}
+
+/// Plays `line` from `game`, which is `ply` moves into the game, along with its variations.
+/// Returns the position at the end of the line
+fn check_line(game: &Game, line: &[PgnMove], ply: usize) -> Result<Game, PgnError> {
+	let mut game = game.clone();
+
+	for (i, pm) in line.iter().enumerate() {
+		for var in &pm.variations {
+			check_line(&game, var, ply + i)?;
+		}
+
+		match pm.mv {
+			Some(mv) => {
+				game.make_move(mv)
+					.map_err(|err| PgnError::Move { ply: ply + i, err })?;
+			}
+			None => game.set_to_move(game.to_move().flip()),
+		}
+	}
+
+	Ok(game)
+}
+
+struct Parser<'a> {
+	src: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn rest(&self) -> &'a str {
+		&self.src[self.pos..]
+	}
+
+	/// Moves past any whitespace and returns the char after it
+	fn skip_ws(&mut self) -> Option<char> {
+		let rest = self.rest();
+		self.pos += rest.len() - rest.trim_start().len();
+
+		self.rest().chars().next()
+	}
+
+	/// The rest of a comment up to its closing `}`, escapes and all, after which parsing
+	/// continues
+	fn comment(&mut self) -> Option<&'a str> {
+		let mut chars = self.rest().char_indices();
+
+		loop {
+			match chars.next()? {
+				(_, '\\') => {
+					chars.next();
+				}
+				(len, '}') => {
+					let text = &self.src[self.pos..self.pos + len];
+					self.pos += len + 1;
+
+					return Some(text);
+				}
+				_ => {}
+			}
+		}
+	}
+
+	fn tag(&mut self) -> Result<(String, String), PgnError> {
+		self.pos += 1;
+
+		let rest = self.rest();
+		let name_len = rest
+			.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+			.unwrap_or(rest.len());
+		let name = rest[..name_len].to_string();
+		self.pos += name_len;
+
+		if name.is_empty() || self.skip_ws() != Some('"') {
+			return Err(PgnError::Tag);
+		}
+		self.pos += 1;
+
+		let mut value = String::new();
+		let mut chars = self.rest().char_indices();
+
+		loop {
+			match chars.next() {
+				Some((_, '\\')) => value.push(chars.next().ok_or(PgnError::Tag)?.1),
+				Some((i, '"')) => {
+					self.pos += i + 1;
+					break;
+				}
+				Some((_, c)) => value.push(c),
+				None => return Err(PgnError::Tag),
+			}
+		}
+
+		if self.skip_ws() != Some(']') {
+			return Err(PgnError::Tag);
+		}
+		self.pos += 1;
+
+		Ok((name, value))
+	}
+
+	/// Reads moves until the end of the game or of the variation, `ply` is the ply of the first
+	fn line(
+		&mut self,
+		ply: usize,
+		in_variation: bool,
+	) -> Result<(Vec<PgnMove>, Option<Outcome>), PgnError> {
+		let mut line: Vec<PgnMove> = vec![];
+
+		loop {
+			match self.skip_ws() {
+				None if in_variation => return Err(PgnError::Unbalanced),
+				None => return Ok((line, None)),
+				Some(')') if in_variation => {
+					self.pos += 1;
+
+					return Ok((line, None));
+				}
+				Some(')' | '}') => return Err(PgnError::Unbalanced),
+				Some('{') => {
+					self.pos += 1;
+
+					let text = self.comment().ok_or(PgnError::Unbalanced)?;
+					let (comment, eval) = split_eval(text);
+					let last = line.last_mut().ok_or(PgnError::Dangling)?;
+
+					// Several comments after a move read as one
+					last.comment = match (last.comment.take(), comment) {
+						(Some(first), Some(then)) => Some(format!("{first} {then}")),
+						(first, then) => first.or(then),
+					};
+					last.eval = eval.or(last.eval);
+				}
+				Some('(') => {
+					self.pos += 1;
+
+					// Played instead of the last move, so it starts at that move's ply
+					let start = ply + line.len();
+					let last = line.last_mut().ok_or(PgnError::Dangling)?;
+					let (var, _) = self.line(start - 1, true)?;
+
+					last.variations.push(var);
+				}
+				Some(_) => {
+					let rest = self.rest();
+					let len = rest
+						.find(|c: char| c.is_whitespace() || "{}()".contains(c))
+						.unwrap_or(rest.len());
+					let word = &rest[..len];
+
+					// Chars that end a word have their own arms above, and an empty word would never
+					// move on
+					if word.is_empty() {
+						return Err(PgnError::Unbalanced);
+					}
+					self.pos += len;
+
+					if let Some(outcome) = Outcome::parse(word) {
+						if in_variation || self.skip_ws().is_some() {
+							return Err(PgnError::Result);
+						}
+
+						return Ok((line, Some(outcome)));
+					}
+
+					// Move numbers like `12.` and `12...`, which can also be stuck to the move
+					let word = match word.find('.') {
+						Some(dot) if word[..dot].bytes().all(|c| c.is_ascii_digit()) => {
+							word[dot..].trim_start_matches('.')
+						}
+						_ => word,
+					};
+
+					if word.is_empty() {
+						continue;
+					}
+
+					if word == "--" {
+						line.push(PgnMove::pass());
+						continue;
+					}
+
+					let mv = word.parse().map_err(|err| PgnError::Move {
+						ply: ply + line.len(),
+						err,
+					})?;
+
+					line.push(PgnMove::new(mv));
+				}
+			}
+		}
+	}
+}
+
+/// Takes the `[%eval n]` out of a comment as written and unescapes the rest, see `escape`
+fn split_eval(text: &str) -> (Option<String>, Option<i32>) {
+	let mut eval = None;
+	let mut text = text.to_string();
+
+	if let Some(start) = text.find("[%eval ")
+		&& let Some(len) = text[start..].find(']')
+		&& let Ok(n) = text[start + 7..start + len].trim().parse()
+	{
+		eval = Some(n);
+		text.replace_range(start..=start + len, "");
+	}
+
+	let mut comment = String::new();
+	let mut chars = text.trim().chars();
+
+	while let Some(c) = chars.next() {
+		comment.push(if c == '\\' {
+			chars.next().unwrap_or(c)
+		} else {
+			c
+		});
+	}
+
+	((!comment.is_empty()).then_some(comment), eval)
+}
+
+/// A comment as it's written, with a `\` before `\`, `}` and the `%` of `[%eval` so that none of
+/// them can end the comment or be read as its evaluation
+fn escape(comment: &str) -> String {
+	comment
+		.replace('\\', "\\\\")
+		.replace('}', "\\}")
+		.replace("[%eval", "[\\%eval")
+}
+
+fn write_line(f: &mut std::fmt::Formatter<'_>, line: &[PgnMove], ply: usize) -> std::fmt::Result {
+	// The number is needed before X's move too when something came between it and O's move
+	let mut numbered = false;
+
+	for (i, pm) in line.iter().enumerate() {
+		let ply = ply + i;
+
+		if i != 0 {
+			f.write_char(' ')?;
+		}
+
+		if ply.is_multiple_of(2) {
+			write!(f, "{}. ", ply / 2 + 1)?;
+		} else if !numbered {
+			write!(f, "{}... ", ply / 2 + 1)?;
+		}
+
+		match pm.mv {
+			Some(mv) => write!(f, "{mv}")?,
+			None => f.write_str("--")?,
+		}
+		numbered = true;
+
+		if pm.comment.is_some() || pm.eval.is_some() {
+			f.write_str(" {")?;
+
+			if let Some(eval) = pm.eval {
+				write!(f, " [%eval {eval}]")?;
+			}
+
+			if let Some(comment) = &pm.comment {
+				write!(f, " {}", escape(comment))?;
+			}
+
+			f.write_str(" }")?;
+			numbered = false;
+		}
+
+		for var in &pm.variations {
+			f.write_str(" (")?;
+			write_line(f, var, ply)?;
+			f.write_char(')')?;
+			numbered = false;
+		}
+	}
+
+	Ok(())
+}
+
+impl Display for Pgn {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (name, value) in &self.tags {
+			let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+			writeln!(f, "[{name} \"{value}\"]")?;
+		}
+
+		if !self.tags.is_empty() {
+			writeln!(f)?;
+		}
+
+		write_line(
+			f,
+			&self.moves,
+			self.start().map_or(0, |start| first_ply(&start)),
+		)?;
+
+		if !self.moves.is_empty() {
+			f.write_char(' ')?;
+		}
+
+		writeln!(f, "{}", self.result)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Outcome, POSITION_TAG, Pgn, PgnError, PgnMove};
+	use crate::{
+		board::{Player, State},
+		game::Game,
+		moves::{Move, MoveError, generate_moves},
+		notation::NotationError,
+		record::GameRecord,
+	};
+
+	const GAME: &str = r#"[X "ultimengine"]
+[O "a \"human\""]
+[Depth "11"]
+
+1. E5 E1 { [%eval -12] the usual reply } 2. A5 (2. A1 A2 (2... A3 { odd } 3. C5)) 2... E9 *
+"#;
+
+	#[test]
+	fn reads_and_writes() {
+		let pgn = Pgn::parse(GAME).unwrap();
+
+		assert_eq!(pgn.tag("O"), Some("a \"human\""));
+		assert_eq!(pgn.tag("Depth"), Some("11"));
+		assert_eq!(pgn.result, Outcome::Unfinished);
+
+		let mvs: Vec<Move> = pgn.moves.iter().filter_map(|pm| pm.mv).collect();
+		assert_eq!(
+			mvs,
+			[
+				Move::new(4, 4),
+				Move::new(4, 0),
+				Move::new(0, 4),
+				Move::new(4, 8)
+			]
+		);
+
+		assert_eq!(pgn.moves[1].eval, Some(-12));
+		assert_eq!(pgn.moves[1].comment.as_deref(), Some("the usual reply"));
+
+		let var = &pgn.moves[2].variations[0];
+		assert_eq!(var[0].mv, Some(Move::new(0, 0)));
+		assert_eq!(var[1].variations[0][0].comment.as_deref(), Some("odd"));
+
+		assert_eq!(pgn.to_string(), GAME);
+		assert_eq!(Pgn::parse(&pgn.to_string()), Ok(pgn));
+	}
+
+	#[test]
+	fn from_moves() {
+		let pgn = Pgn::parse("1.e5 e1 2.a5 *").unwrap();
+
+		assert_eq!(pgn.result, Outcome::Unfinished);
+		assert_eq!(
+			pgn.game().ok(),
+			Game::new()
+				.sim_move(Move::new(4, 4))
+				.and_then(|g| g.sim_move(Move::new(4, 0)))
+				.and_then(|g| g.sim_move(Move::new(0, 4)))
+				.ok()
+		);
+
+		let mut pgn = Pgn::new([Move::new(4, 4)]);
+		pgn.moves.push(PgnMove::new(Move::new(4, 0)));
+		pgn.set_tag("Result", "*");
+		assert_eq!(pgn.to_string(), "[Result \"*\"]\n\n1. E5 E1 *\n");
+
+		assert_eq!(
+			Game::from_moves([Move::new(4, 4), Move::new(0, 0)]),
+			Err(MoveError::WrongBoard { active: 4 })
+		);
+	}
+
+	#[test]
+	fn rejects_invalid() {
+		assert_eq!(Pgn::parse("[X ultimengine]"), Err(PgnError::Tag));
+		assert_eq!(Pgn::parse("1. E5 { open"), Err(PgnError::Unbalanced));
+		assert_eq!(Pgn::parse("1. E5 (E1"), Err(PgnError::Unbalanced));
+		assert_eq!(Pgn::parse("1. E5 )"), Err(PgnError::Unbalanced));
+		assert_eq!(Pgn::parse("1. E5 } E1"), Err(PgnError::Unbalanced));
+		assert_eq!(Pgn::parse("1. E5 (E1 })"), Err(PgnError::Unbalanced));
+		assert_eq!(Pgn::parse("{ hi } 1. E5"), Err(PgnError::Dangling));
+		assert_eq!(Pgn::parse("(1. E5) 1. E5"), Err(PgnError::Dangling));
+		assert_eq!(Pgn::parse("1. E5 E1 ((E2))"), Err(PgnError::Dangling));
+		assert_eq!(Pgn::parse("1. E5 E1 ({ hi } E2)"), Err(PgnError::Dangling));
+		assert_eq!(Pgn::parse("1. E5 1-0 E1"), Err(PgnError::Result));
+		assert_eq!(
+			Pgn::parse("1. E5 1-0"),
+			Err(PgnError::WrongResult {
+				found: Outcome::XWon,
+				expected: Outcome::Unfinished
+			})
+		);
+		assert_eq!(
+			Pgn::parse("1. E5 Z1"),
+			Err(PgnError::Move {
+				ply: 1,
+				err: MoveError::ParseBoard
+			})
+		);
+		assert_eq!(
+			Pgn::parse("1. E5 E1 (1... A1) *"),
+			Err(PgnError::Move {
+				ply: 1,
+				err: MoveError::WrongBoard { active: 4 }
+			})
+		);
+	}
+
+	#[test]
+	fn checks_result() {
+		// Always the first legal move, until someone wins
+		let mut game = Game::new();
+		let mut pgn = Pgn::default();
+
+		while game.state() == State::Undecided {
+			let mv = generate_moves(&game)[0];
+
+			game.make_move(mv).unwrap();
+			pgn.moves.push(PgnMove::new(mv));
+		}
+
+		let outcome = Outcome::from_state(game.state());
+		pgn.result = outcome;
+		assert_eq!(Pgn::parse(&pgn.to_string()), Ok(pgn.clone()));
+
+		// Left off, the result comes from the final position
+		let text = pgn.to_string();
+		let moves = text.trim_end().trim_end_matches(outcome.as_str());
+		assert_eq!(Pgn::parse(moves).map(|pgn| pgn.result), Ok(outcome));
+
+		pgn.result = Outcome::Unfinished;
+		assert_eq!(
+			Pgn::parse(&pgn.to_string()),
+			Err(PgnError::WrongResult {
+				found: Outcome::Unfinished,
+				expected: outcome
+			})
+		);
+	}
+
+	#[test]
+	fn comments() {
+		let pgn = Pgn::parse("1. E5 { one } { [%eval 4] two } E1 *").unwrap();
+		assert_eq!(pgn.moves[0].comment.as_deref(), Some("one two"));
+		assert_eq!(pgn.moves[0].eval, Some(4));
+
+		let mut pgn = Pgn::new([Move::new(4, 4)]);
+		pgn.moves[0].comment = Some(r"a } b [%eval 3] c \ d \".to_string());
+		pgn.moves[0].eval = Some(-2);
+
+		let text = pgn.to_string();
+		assert_eq!(
+			text,
+			"1. E5 { [%eval -2] a \\} b [\\%eval 3] c \\\\ d \\\\ } *\n"
+		);
+		assert_eq!(Pgn::parse(&text), Ok(pgn));
+	}
+
+	#[test]
+	fn start_positions() {
+		// O starts after a skip, and passes later on
+		let mut record = GameRecord::new();
+		record.set_to_move(Player::O);
+		record.make_move(Move::new(4, 4)).unwrap();
+		record.make_move(Move::new(4, 0)).unwrap();
+		record.set_to_move(Player::X);
+		record.make_move(Move::new(0, 8)).unwrap();
+
+		let pgn = Pgn::from_record(&record);
+		assert_eq!(pgn.to_string(), "1. -- E5 2. E1 -- 3. A9 *\n");
+		assert_eq!(
+			Pgn::parse(&pgn.to_string()).unwrap().game(),
+			Ok(record.game().clone())
+		);
+
+		// A loaded position is written as a tag, and O moving first is numbered like it
+		let start = Game::from_notation(
+			"xx.o...../........./........./........./........./........./........./\
+			........./......... --------- o a",
+		)
+		.unwrap();
+		let mut record = GameRecord::from_game(start.clone());
+		record.make_move(Move::new(0, 2)).unwrap();
+
+		let pgn = Pgn::from_record(&record);
+		let text = pgn.to_string();
+		assert!(text.ends_with("\n\n1... A3 *\n"), "{text}");
+
+		let read = Pgn::parse(&text).unwrap();
+		assert_eq!(read.start(), Ok(start));
+		assert_eq!(read.game(), Ok(record.game().clone()));
+
+		let mut bad = pgn.clone();
+		bad.set_tag(POSITION_TAG, "xx");
+		assert_eq!(
+			Pgn::parse(&bad.to_string()),
+			Err(PgnError::Position(NotationError::Fields))
+		);
+	}
+}
@@ -0,0 +1,270 @@
+//! A game together with the moves that were played in it, so they can be taken back and replayed
+
+use crate::{
+	board::Player,
+	game::{Game, Undo},
+	moves::{Move, MoveError},
+};
+
+/// One step of a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+	/// A move, along with what's needed to take it back
+	Move(Undo),
+	/// The turn handed on without a move, by the side that had it
+	Pass(Player),
+}
+
+impl Entry {
+	/// The move played, `None` for a pass
+	#[inline]
+	pub const fn mv(&self) -> Option<Move> {
+		match self {
+			Entry::Move(undo) => Some(undo.mv),
+			Entry::Pass(_) => None,
+		}
+	}
+
+	/// The side whose turn it was
+	#[inline]
+	pub const fn side(&self) -> Player {
+		match self {
+			Entry::Move(undo) => undo.side,
+			Entry::Pass(side) => *side,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+	game: Game,
+	/// Every move and pass so far, oldest first
+	played: Vec<Entry>,
+	/// Entries taken back with `undo`, the most recent one last
+	undone: Vec<Entry>,
+}
+
+impl GameRecord {
+	pub fn new() -> Self {
+		Self::from_game(Game::new())
+	}
+
+	/// A record starting from `game`, which can't be undone past
+	pub fn from_game(game: Game) -> Self {
+		GameRecord {
+			game,
+			played: vec![],
+			undone: vec![],
+		}
+	}
+
+	#[inline]
+	pub fn game(&self) -> &Game {
+		&self.game
+	}
+
+	/// Plays `mv` for the side to move, which drops any moves that could be redone
+	pub fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+		let undo = self.game.make_move(mv)?;
+
+		self.played.push(Entry::Move(undo));
+		self.undone.clear();
+
+		Ok(())
+	}
+
+	/// `make_move`, but only if it's `side`'s turn
+	pub fn make_move_as(&mut self, mv: Move, side: Player) -> Result<(), MoveError> {
+		let undo = self.game.make_move_as(mv, side)?;
+
+		self.played.push(Entry::Move(undo));
+		self.undone.clear();
+
+		Ok(())
+	}
+
+	/// Hands the turn to `side` without playing a move, which is recorded as a pass unless it's
+	/// already `side`'s turn
+	pub fn set_to_move(&mut self, side: Player) {
+		let from = self.game.to_move();
+
+		if from != side {
+			self.game.set_to_move(side);
+			self.played.push(Entry::Pass(from));
+			self.undone.clear();
+		}
+	}
+
+	/// Takes back the last move or pass, if there is one
+	pub fn undo(&mut self) -> Option<Entry> {
+		let entry = self.played.pop()?;
+
+		match entry {
+			Entry::Move(undo) => self.game.unmake_move(undo),
+			Entry::Pass(side) => self.game.set_to_move(side),
+		}
+		self.undone.push(entry);
+
+		Some(entry)
+	}
+
+	/// Plays the last move or pass taken back again, if there is one
+	pub fn redo(&mut self) -> Option<Entry> {
+		let entry = self.undone.pop()?;
+
+		// The game is back where the entry was first made, so its side is the one to move
+		self.game.set_to_move(entry.side());
+		match entry {
+			Entry::Move(undo) => {
+				// It was legal when it was first played, from this same position
+				self.game.make_move_unchecked(undo.mv);
+			}
+			Entry::Pass(side) => self.game.set_to_move(side.flip()),
+		}
+		self.played.push(entry);
+
+		Some(entry)
+	}
+
+	/// The position the record started from
+	pub fn start(&self) -> Game {
+		let mut game = self.game.clone();
+
+		for entry in self.played.iter().rev() {
+			match *entry {
+				Entry::Move(undo) => game.unmake_move(undo),
+				Entry::Pass(side) => game.set_to_move(side),
+			}
+		}
+
+		game
+	}
+
+	/// Every move and pass so far, oldest first
+	pub fn entries(&self) -> impl ExactSizeIterator<Item = Entry> + '_ {
+		self.played.iter().copied()
+	}
+
+	/// What `redo` would play, the next one first
+	pub fn redoable(&self) -> impl ExactSizeIterator<Item = Entry> + '_ {
+		self.undone.iter().rev().copied()
+	}
+
+	/// The moves played so far, oldest first, leaving out passes
+	pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+		self.played.iter().filter_map(Entry::mv)
+	}
+
+	/// The last move, `None` if there isn't one or the turn was passed since
+	#[inline]
+	pub fn last_move(&self) -> Option<Move> {
+		self.played.last().and_then(Entry::mv)
+	}
+
+	/// How many moves have been made, leaving out passes
+	pub fn ply(&self) -> usize {
+		self.moves().count()
+	}
+}
+
+impl Default for GameRecord {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Entry, GameRecord};
+	use crate::{
+		board::{Player, State},
+		game::Game,
+		moves::{Move, generate_moves},
+	};
+
+	#[test]
+	fn undo_redo() {
+		let mut record = GameRecord::new();
+		let mut games = vec![record.game().clone()];
+		let mut moves = vec![];
+
+		for ply in 0..40 {
+			let lgms = generate_moves(record.game());
+
+			if lgms.is_empty() || record.game().state() != State::Undecided {
+				break;
+			}
+
+			let mv = lgms[(ply * 7) % lgms.len()];
+			record.make_move(mv).unwrap();
+
+			moves.push(mv);
+			games.push(record.game().clone());
+		}
+
+		assert_eq!(record.moves().collect::<Vec<_>>(), moves);
+
+		for (ply, mv) in moves.iter().enumerate().rev() {
+			assert_eq!(record.undo().and_then(|e| e.mv()), Some(*mv));
+			assert_eq!(record.ply(), ply);
+			assert_eq!(record.game(), &games[ply]);
+		}
+
+		assert_eq!(record.undo(), None);
+		assert_eq!(record.game(), &Game::new());
+
+		for (ply, mv) in moves.iter().enumerate() {
+			assert_eq!(record.redo().and_then(|e| e.mv()), Some(*mv));
+			assert_eq!(record.game(), &games[ply + 1]);
+		}
+
+		assert_eq!(record.redo(), None);
+	}
+
+	#[test]
+	fn move_clears_redo() {
+		let mut record = GameRecord::new();
+
+		record.make_move(Move::new(4, 4)).unwrap();
+		record.make_move(Move::new(4, 0)).unwrap();
+		record.undo();
+
+		record.make_move(Move::new(4, 8)).unwrap();
+
+		assert_eq!(record.redo(), None);
+		assert_eq!(record.last_move(), Some(Move::new(4, 8)));
+		assert_eq!(record.ply(), 2);
+	}
+
+	#[test]
+	fn passes() {
+		let mut record = GameRecord::new();
+
+		// Already O's turn after X's move, so nothing to record
+		record.make_move(Move::new(4, 4)).unwrap();
+		record.set_to_move(Player::O);
+		assert_eq!(record.ply(), 1);
+		record.undo();
+
+		record.set_to_move(Player::O);
+		record.make_move(Move::new(4, 4)).unwrap();
+		let after = record.game().clone();
+
+		assert_eq!(record.moves().collect::<Vec<_>>(), [Move::new(4, 4)]);
+		assert_eq!(record.start(), Game::new());
+
+		assert!(matches!(record.undo(), Some(Entry::Move(_))));
+		assert_eq!(record.undo(), Some(Entry::Pass(Player::X)));
+		assert_eq!(record.game(), &Game::new());
+		assert_eq!(record.game().to_move(), Player::X);
+		assert_eq!(record.undo(), None);
+
+		assert_eq!(record.redoable().count(), 2);
+		assert_eq!(record.redo(), Some(Entry::Pass(Player::X)));
+		assert_eq!(record.game().to_move(), Player::O);
+		assert_eq!(record.redo().and_then(|e| e.mv()), Some(Move::new(4, 4)));
+		assert_eq!(record.game(), &after);
+		assert_eq!(record.last_move(), Some(Move::new(4, 4)));
+		assert_eq!(record.ply(), 1);
+	}
+}
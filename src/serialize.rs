@@ -0,0 +1,331 @@
+//! `Serialize` and `Deserialize` for the position types, behind the `serde` feature
+//!
+//! Everything is written the way a person would read it: moves as `E5`, boards as their 9 cells
+//! and their state, games as their boards, the side to move and the active board, and game
+//! records (`pgn`) as their text. Deserializing checks the values like the text formats do, so a
+//! position that can't come up in a game is an error rather than a `Game` the engine trips over.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::{
+	bitboard::BitBoard,
+	board::{Player, Slot, State},
+	game::{Game, decided_state},
+	lut,
+	moves::Move,
+	pgn::Pgn,
+	record::GameRecord,
+};
+
+impl Serialize for Move {
+	fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+		s.collect_str(self)
+	}
+}
+
+/// Any notation `Move::from_str` reads
+impl<'de> Deserialize<'de> for Move {
+	fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+		String::deserialize(d)?.parse().map_err(de::Error::custom)
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "BitBoard")]
+struct BoardRepr {
+	/// From the top left
+	cells: [Slot; 9],
+	state: State,
+}
+
+impl Serialize for BitBoard {
+	fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+		BoardRepr {
+			cells: self.to_arr(),
+			state: self.state(),
+		}
+		.serialize(s)
+	}
+}
+
+/// Boards on their own can be meta-boards, so disabled cells are fine here but not in a `Game`.
+/// The state has to be the one the cells give
+impl<'de> Deserialize<'de> for BitBoard {
+	fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+		let repr = BoardRepr::deserialize(d)?;
+		let mut brd = BitBoard::new_with(repr.cells);
+
+		if lut::won_by_x(brd) && lut::won_by_o(brd) {
+			return Err(de::Error::custom(
+				"both sides have a line in the same board",
+			));
+		}
+
+		let expected = decided_state(brd);
+		if repr.state != expected {
+			return Err(de::Error::custom(format_args!(
+				"board state is {:?} but its cells make it {expected:?}",
+				repr.state
+			)));
+		}
+
+		brd.set_state(expected);
+
+		Ok(brd)
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Game")]
+struct GameRepr {
+	boards: [BitBoard; 9],
+	to_move: Player,
+	/// The board's letter, `None` when any board can be played in
+	active: Option<char>,
+	/// Always written, worked out from the boards when reading and only checked if it's there
+	#[serde(default)]
+	state: Option<State>,
+}
+
+impl Serialize for Game {
+	fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+		GameRepr {
+			boards: self.boards,
+			to_move: self.to_move(),
+			active: (self.active != 9).then(|| (self.active + b'a') as char),
+			state: Some(self.state()),
+		}
+		.serialize(s)
+	}
+}
+
+impl<'de> Deserialize<'de> for Game {
+	fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+		let repr = GameRepr::deserialize(d)?;
+
+		let active = match repr.active.map(|c| c.to_ascii_lowercase()) {
+			None => 9,
+			Some(c @ 'a'..='i') => c as u8 - b'a',
+			Some(_) => {
+				return Err(de::Error::custom(
+					"active board must be a letter from a to i",
+				));
+			}
+		};
+
+		let game =
+			Game::from_boards(repr.boards, repr.to_move, active).map_err(de::Error::custom)?;
+
+		if repr.state.is_some_and(|st| st != game.state()) {
+			return Err(de::Error::custom("game state doesn't match its boards"));
+		}
+
+		Ok(game)
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "GameRecord")]
+struct RecordRepr {
+	start: Game,
+	/// Oldest first, `null` for a pass
+	moves: Vec<Option<Move>>,
+	/// Moves that can be redone, the next one first
+	#[serde(default)]
+	redo: Vec<Option<Move>>,
+}
+
+impl Serialize for GameRecord {
+	fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+		RecordRepr {
+			start: self.start(),
+			moves: self.entries().map(|entry| entry.mv()).collect(),
+			redo: self.redoable().map(|entry| entry.mv()).collect(),
+		}
+		.serialize(s)
+	}
+}
+
+impl<'de> Deserialize<'de> for GameRecord {
+	fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+		let repr = RecordRepr::deserialize(d)?;
+		let mut record = GameRecord::from_game(repr.start);
+
+		for mv in repr.moves.iter().chain(&repr.redo) {
+			match mv {
+				Some(mv) => record
+					.make_move(*mv)
+					.map_err(|e| de::Error::custom(format_args!("{mv}: {e}")))?,
+				None => record.set_to_move(record.game().to_move().flip()),
+			}
+		}
+
+		for _ in &repr.redo {
+			record.undo();
+		}
+
+		Ok(record)
+	}
+}
+
+impl Serialize for Pgn {
+	fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+		s.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for Pgn {
+	fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+		Pgn::parse(&String::deserialize(d)?).map_err(de::Error::custom)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use serde_json::{Value, json};
+
+	use crate::{
+		bitboard::BitBoard,
+		board::{Player, Slot, State},
+		counting::{SearchResult, search_with},
+		eval::{EvalParams, Explanation},
+		game::Game,
+		moves::Move,
+		pgn::Pgn,
+		record::GameRecord,
+	};
+
+	#[test]
+	fn round_trips() {
+		for times in 0..70 {
+			let game = Game::random(times);
+			let json = serde_json::to_string(&game).unwrap();
+
+			assert_eq!(serde_json::from_str::<Game>(&json).unwrap(), game);
+		}
+
+		let mut record = GameRecord::new();
+		for mv in [Move::new(4, 4), Move::new(4, 0), Move::new(0, 4)] {
+			record.make_move(mv).unwrap();
+		}
+		record.undo();
+
+		let json = serde_json::to_value(&record).unwrap();
+		assert_eq!(json["moves"], json!(["E5", "E1"]));
+		assert_eq!(json["redo"], json!(["A5"]));
+
+		let mut back: GameRecord = serde_json::from_value(json).unwrap();
+		assert_eq!(back.game(), record.game());
+		assert_eq!(back.redo().and_then(|e| e.mv()), Some(Move::new(0, 4)));
+
+		let mut skipped = GameRecord::new();
+		skipped.set_to_move(Player::O);
+		skipped.make_move(Move::new(4, 4)).unwrap();
+
+		let json = serde_json::to_value(&skipped).unwrap();
+		assert_eq!(json["moves"], json!([null, "E5"]));
+
+		let back: GameRecord = serde_json::from_value(json).unwrap();
+		assert_eq!(back.game(), skipped.game());
+		assert_eq!(back.start(), Game::new());
+
+		let pgn = Pgn::parse("1. E5 E1 { [%eval 3] } (1... E9) *").unwrap();
+		let json = serde_json::to_string(&pgn).unwrap();
+		assert_eq!(serde_json::from_str::<Pgn>(&json).unwrap(), pgn);
+	}
+
+	#[test]
+	fn readable() {
+		let game = Game::new().sim_move(Move::new(4, 4)).unwrap();
+		let json = serde_json::to_value(&game).unwrap();
+
+		assert_eq!(json["to_move"], "o");
+		assert_eq!(json["active"], "e");
+		assert_eq!(json["state"], "undecided");
+		assert_eq!(json["boards"][4]["cells"][4], "x");
+		assert_eq!(json["boards"][4]["state"], "undecided");
+
+		assert_eq!(serde_json::to_value(Move::new(0, 8)).unwrap(), "A9");
+		assert_eq!(
+			serde_json::from_value::<Move>(json!("4,4")).unwrap(),
+			Move::new(4, 4)
+		);
+		assert_eq!(serde_json::to_value(Player::O).unwrap(), "o");
+		assert_eq!(serde_json::to_value(State::Tied).unwrap(), "tied");
+
+		let result = SearchResult::from(search_with::<3>(&game, &EvalParams::DEFAULT));
+		let out = serde_json::to_value(result).unwrap();
+		assert_eq!(out["mv"], result.mv.unwrap().to_string());
+		assert_eq!(serde_json::from_value::<SearchResult>(out).unwrap(), result);
+
+		let mut over = game.clone();
+		over.set_state(State::Won);
+		let result = SearchResult::from(search_with::<3>(&over, &EvalParams::DEFAULT));
+		assert_eq!(result.mv, None);
+		assert!(serde_json::to_value(result).unwrap()["mv"].is_null());
+
+		let explained = EvalParams::DEFAULT.explain(&game, Slot::X);
+		let out = serde_json::to_value(&explained).unwrap();
+		assert_eq!(out["boards"][4]["won_by"], "empty");
+		assert_eq!(
+			serde_json::from_value::<Explanation>(out).unwrap(),
+			explained
+		);
+
+		// `state` can be left out
+		let mut json = json;
+		json.as_object_mut().unwrap().remove("state");
+		assert_eq!(serde_json::from_value::<Game>(json).unwrap(), game);
+	}
+
+	#[test]
+	fn rejects_invalid() {
+		let game = serde_json::to_value(Game::new().sim_move(Move::new(4, 4)).unwrap()).unwrap();
+		let with = |f: &dyn Fn(&mut Value)| {
+			let mut json = game.clone();
+			f(&mut json);
+
+			serde_json::from_value::<Game>(json).is_err()
+		};
+
+		assert!(!with(&|_| {}));
+		assert!(with(&|j| j["boards"][4]["state"] = json!("won")));
+		assert!(with(&|j| j["boards"][0]["cells"][0] = json!("disabled")));
+		assert!(with(&|j| j["boards"].as_array_mut().unwrap().truncate(8)));
+		assert!(!with(&|j| j["active"] = json!("E")));
+		assert!(with(&|j| j["active"] = json!("j")));
+		assert!(with(&|j| j["active"] = json!(4)));
+		assert!(with(&|j| j["state"] = json!("won")));
+		assert!(with(&|j| j["to_move"] = json!("empty")));
+
+		let board = |cells: &str, state: &str| {
+			let cells: Vec<_> = cells
+				.chars()
+				.map(|c| match c {
+					'x' => "x",
+					'o' => "o",
+					'#' => "disabled",
+					_ => "empty",
+				})
+				.collect();
+
+			serde_json::from_value::<BitBoard>(json!({ "cells": cells, "state": state }))
+		};
+
+		assert!(board("xxx.oo...", "won").is_ok());
+		assert!(board("xxx.oo...", "undecided").is_err());
+		assert!(board("xxxooo...", "won").is_err());
+		assert!(board("x..o.#...", "undecided").is_ok());
+		assert!(board("xo#xo#ox#", "tied").is_ok());
+
+		assert!(serde_json::from_value::<Move>(json!("J5")).is_err());
+		assert!(serde_json::from_value::<Pgn>(json!("1. E5 A1")).is_err());
+		assert!(
+			serde_json::from_value::<GameRecord>(json!({
+				"start": serde_json::to_value(Game::new()).unwrap(),
+				"moves": ["E5", "A1"],
+			}))
+			.is_err()
+		);
+	}
+}
@@ -0,0 +1,393 @@
+//! x86_64 SIMD versions of the mask scans in `BitBoard` and `counting`
+//!
+//! The scans test one board against many masks, so SSE2 tests 4 masks per instruction and AVX2
+//! tests 8. Which one runs is picked at runtime with `is_x86_feature_detected!`, other targets
+//! (and CPUs without either) use the existing scans.
+//!
+//! Single boards are scored through the tables in `lut`, which beat any of these, so the search
+//! doesn't use this module. What does is `batch`, through `Backend::count_many`.
+
+use std::sync::OnceLock;
+
+use crate::{
+	bitboard::BitBoard,
+	counting,
+	generated::{ONE_AWAY_O, ONE_AWAY_X, POSSIBLE_TO_WIN, WON_BY_O, WON_BY_X},
+};
+
+/// How many entries of `POSSIBLE_TO_WIN` `counting::possible_to_win` looks at
+const POSSIBLE_MASKS: usize = 88;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+	/// The implementations in `BitBoard` and `counting`, which use NEON on aarch64
+	Scalar,
+	Sse2,
+	Avx2,
+}
+
+impl Backend {
+	pub const ALL: [Backend; 3] = [Backend::Scalar, Backend::Sse2, Backend::Avx2];
+
+	/// The fastest backend this CPU supports, only looked up the first time
+	#[inline]
+	pub fn detect() -> Backend {
+		static DETECTED: OnceLock<Backend> = OnceLock::new();
+
+		*DETECTED.get_or_init(|| {
+			if Backend::Avx2.is_supported() {
+				Backend::Avx2
+			} else if Backend::Sse2.is_supported() {
+				Backend::Sse2
+			} else {
+				Backend::Scalar
+			}
+		})
+	}
+
+	#[inline]
+	pub fn is_supported(self) -> bool {
+		match self {
+			Backend::Scalar => true,
+			#[cfg(target_arch = "x86_64")]
+			Backend::Sse2 => is_x86_feature_detected!("sse2"),
+			#[cfg(target_arch = "x86_64")]
+			Backend::Avx2 => is_x86_feature_detected!("avx2"),
+			#[cfg(not(target_arch = "x86_64"))]
+			_ => false,
+		}
+	}
+
+	/// Number of `masks` fully set in `board`, `None` if this backend isn't a SIMD one or the CPU
+	/// doesn't support it
+	#[inline]
+	fn count(self, board: u32, masks: &[u32]) -> Option<u32> {
+		match self {
+			#[cfg(target_arch = "x86_64")]
+			Backend::Avx2 if self.is_supported() => Some(unsafe { x86::count_avx2(board, masks) }),
+			#[cfg(target_arch = "x86_64")]
+			Backend::Sse2 if self.is_supported() => Some(unsafe { x86::count_sse2(board, masks) }),
+			_ => None,
+		}
+	}
+
+	/// Whether any of `masks` is fully set in `board`, see `count`
+	#[inline]
+	fn any(self, board: u32, masks: &[u32]) -> Option<bool> {
+		match self {
+			#[cfg(target_arch = "x86_64")]
+			Backend::Avx2 if self.is_supported() => Some(unsafe { x86::any_avx2(board, masks) }),
+			#[cfg(target_arch = "x86_64")]
+			Backend::Sse2 if self.is_supported() => Some(unsafe { x86::any_sse2(board, masks) }),
+			_ => None,
+		}
+	}
+
+	pub fn one_aways_x(self, board: BitBoard) -> i32 {
+		self.count(board.0, &ONE_AWAY_X)
+			.map_or_else(|| board.one_aways_x(), |n| n as i32)
+	}
+
+	pub fn one_aways_o(self, board: BitBoard) -> i32 {
+		self.count(board.0, &ONE_AWAY_O)
+			.map_or_else(|| board.one_aways_o(), |n| n as i32)
+	}
+
+	pub fn won_by_x(self, board: BitBoard) -> bool {
+		self.any(board.0, &WON_BY_X)
+			.unwrap_or_else(|| board.won_by_x())
+	}
+
+	pub fn won_by_o(self, board: BitBoard) -> bool {
+		self.any(board.0, &WON_BY_O)
+			.unwrap_or_else(|| board.won_by_o())
+	}
+
+	pub fn possible_to_win(self, board: BitBoard) -> bool {
+		self.any(board.0, &POSSIBLE_TO_WIN[..POSSIBLE_MASKS])
+			.unwrap_or_else(|| counting::possible_to_win(board))
+	}
+
+	/// For every board in `boards`, the number of `masks` fully set in it. Unlike the methods
+	/// above this tests one mask against several boards per instruction
+	pub fn count_many(self, boards: &[u32], masks: &[u32], out: &mut [u32]) {
+		debug_assert_eq!(boards.len(), out.len());
+
+		let lanes = match self {
+			#[cfg(target_arch = "x86_64")]
+			Backend::Avx2 if self.is_supported() => 8,
+			#[cfg(target_arch = "x86_64")]
+			Backend::Sse2 if self.is_supported() => 4,
+			_ => 1,
+		};
+
+		let chunks = boards.chunks_exact(lanes);
+		let rest = chunks.remainder();
+		let mut outs = out.chunks_exact_mut(lanes);
+
+		for (brds, out) in chunks.zip(outs.by_ref()) {
+			match lanes {
+				#[cfg(target_arch = "x86_64")]
+				8 => unsafe { x86::count_lanes_avx2(brds, masks, out) },
+				#[cfg(target_arch = "x86_64")]
+				4 => unsafe { x86::count_lanes_sse2(brds, masks, out) },
+				_ => count_lanes(brds, masks, out),
+			}
+		}
+
+		count_lanes(rest, masks, outs.into_remainder());
+	}
+}
+
+/// Scalar `Backend::count_many`
+fn count_lanes(boards: &[u32], masks: &[u32], out: &mut [u32]) {
+	for (&brd, out) in boards.iter().zip(out) {
+		*out = count_scalar(brd, masks);
+	}
+}
+
+/// Number of `masks` fully set in `board`, for what's left over after the SIMD chunks
+#[inline]
+fn count_scalar(board: u32, masks: &[u32]) -> u32 {
+	masks.iter().filter(|&&m| m & board == m).count() as u32
+}
+
+/// `BitBoard::one_aways_x` on the fastest backend
+#[inline]
+pub fn one_aways_x(board: BitBoard) -> i32 {
+	Backend::detect().one_aways_x(board)
+}
+
+/// `BitBoard::one_aways_o` on the fastest backend
+#[inline]
+pub fn one_aways_o(board: BitBoard) -> i32 {
+	Backend::detect().one_aways_o(board)
+}
+
+/// `BitBoard::won_by_x` on the fastest backend
+#[inline]
+pub fn won_by_x(board: BitBoard) -> bool {
+	Backend::detect().won_by_x(board)
+}
+
+/// `BitBoard::won_by_o` on the fastest backend
+#[inline]
+pub fn won_by_o(board: BitBoard) -> bool {
+	Backend::detect().won_by_o(board)
+}
+
+/// `counting::possible_to_win` on the fastest backend
+#[inline]
+pub fn possible_to_win(board: BitBoard) -> bool {
+	Backend::detect().possible_to_win(board)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+	use std::arch::x86_64::{
+		__m128i, __m256i, _mm_and_si128, _mm_castsi128_ps, _mm_cmpeq_epi32, _mm_loadu_si128,
+		_mm_movemask_ps, _mm_set1_epi32, _mm_setzero_si128, _mm_storeu_si128, _mm_sub_epi32,
+		_mm256_and_si256, _mm256_castsi256_ps, _mm256_cmpeq_epi32, _mm256_loadu_si256,
+		_mm256_movemask_ps, _mm256_set1_epi32, _mm256_setzero_si256, _mm256_storeu_si256,
+		_mm256_sub_epi32,
+	};
+
+	/// Bit n is set if mask n of the 4 at `masks` is fully set in `brd`
+	#[inline]
+	#[target_feature(enable = "sse2")]
+	fn matches_sse2(brd: __m128i, masks: &[u32]) -> u32 {
+		debug_assert_eq!(masks.len(), 4);
+
+		let masks = unsafe { _mm_loadu_si128(masks.as_ptr() as *const __m128i) };
+		let eq = _mm_cmpeq_epi32(_mm_and_si128(masks, brd), masks);
+
+		_mm_movemask_ps(_mm_castsi128_ps(eq)) as u32
+	}
+
+	/// Bit n is set if mask n of the 8 at `masks` is fully set in `brd`
+	#[inline]
+	#[target_feature(enable = "avx2")]
+	fn matches_avx2(brd: __m256i, masks: &[u32]) -> u32 {
+		debug_assert_eq!(masks.len(), 8);
+
+		let masks = unsafe { _mm256_loadu_si256(masks.as_ptr() as *const __m256i) };
+		let eq = _mm256_cmpeq_epi32(_mm256_and_si256(masks, brd), masks);
+
+		_mm256_movemask_ps(_mm256_castsi256_ps(eq)) as u32
+	}
+
+	/// Needs SSE2
+	#[target_feature(enable = "sse2")]
+	pub unsafe fn count_sse2(board: u32, masks: &[u32]) -> u32 {
+		let brd = _mm_set1_epi32(board as i32);
+
+		let chunks = masks.chunks_exact(4);
+		let rest = super::count_scalar(board, chunks.remainder());
+
+		chunks
+			.map(|chunk| matches_sse2(brd, chunk).count_ones())
+			.sum::<u32>()
+			+ rest
+	}
+
+	/// Needs SSE2
+	#[target_feature(enable = "sse2")]
+	pub unsafe fn any_sse2(board: u32, masks: &[u32]) -> bool {
+		let brd = _mm_set1_epi32(board as i32);
+
+		let mut chunks = masks.chunks_exact(4);
+		let rest = chunks.remainder();
+
+		chunks.any(|chunk| matches_sse2(brd, chunk) != 0) || super::count_scalar(board, rest) != 0
+	}
+
+	/// Needs AVX2
+	#[target_feature(enable = "avx2")]
+	pub unsafe fn count_avx2(board: u32, masks: &[u32]) -> u32 {
+		let brd = _mm256_set1_epi32(board as i32);
+
+		let chunks = masks.chunks_exact(8);
+		let rest = super::count_scalar(board, chunks.remainder());
+
+		chunks
+			.map(|chunk| matches_avx2(brd, chunk).count_ones())
+			.sum::<u32>()
+			+ rest
+	}
+
+	/// Needs AVX2
+	#[target_feature(enable = "avx2")]
+	pub unsafe fn any_avx2(board: u32, masks: &[u32]) -> bool {
+		let brd = _mm256_set1_epi32(board as i32);
+
+		let mut chunks = masks.chunks_exact(8);
+		let rest = chunks.remainder();
+
+		chunks.any(|chunk| matches_avx2(brd, chunk) != 0) || super::count_scalar(board, rest) != 0
+	}
+
+	/// Needs SSE2, `boards` and `out` are 4 long
+	#[target_feature(enable = "sse2")]
+	pub unsafe fn count_lanes_sse2(boards: &[u32], masks: &[u32], out: &mut [u32]) {
+		debug_assert!(boards.len() == 4 && out.len() == 4);
+
+		let brds = unsafe { _mm_loadu_si128(boards.as_ptr() as *const __m128i) };
+		let mut counts = _mm_setzero_si128();
+
+		for &mask in masks {
+			let mask = _mm_set1_epi32(mask as i32);
+
+			// Matching lanes are all ones, which is -1
+			counts = _mm_sub_epi32(counts, _mm_cmpeq_epi32(_mm_and_si128(brds, mask), mask));
+		}
+
+		unsafe { _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, counts) };
+	}
+
+	/// Needs AVX2, `boards` and `out` are 8 long
+	#[target_feature(enable = "avx2")]
+	pub unsafe fn count_lanes_avx2(boards: &[u32], masks: &[u32], out: &mut [u32]) {
+		debug_assert!(boards.len() == 8 && out.len() == 8);
+
+		let brds = unsafe { _mm256_loadu_si256(boards.as_ptr() as *const __m256i) };
+		let mut counts = _mm256_setzero_si256();
+
+		for &mask in masks {
+			let mask = _mm256_set1_epi32(mask as i32);
+
+			counts = _mm256_sub_epi32(
+				counts,
+				_mm256_cmpeq_epi32(_mm256_and_si256(brds, mask), mask),
+			);
+		}
+
+		unsafe { _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, counts) };
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Backend;
+	use crate::{
+		bitboard::BitBoard,
+		board::Slot::{self, Empty, O, X},
+		counting,
+		generated::ONE_AWAY_X,
+	};
+
+	fn all_boards() -> impl Iterator<Item = BitBoard> {
+		(0..3u32.pow(9)).map(|mut n| {
+			let cells: [Slot; 9] = std::array::from_fn(|_| {
+				let cell = [Empty, X, O][n as usize % 3];
+				n /= 3;
+
+				cell
+			});
+
+			BitBoard::new_with(cells)
+		})
+	}
+
+	#[test]
+	fn count_many_agrees() {
+		let boards: Vec<u32> = all_boards().map(|b| b.0).collect();
+		let mut out = vec![0; boards.len()];
+
+		for backend in Backend::ALL.into_iter().filter(|b| b.is_supported()) {
+			// Odd lengths so the scalar remainder gets used too
+			for len in [boards.len(), 13, 3] {
+				backend.count_many(&boards[..len], &ONE_AWAY_X, &mut out[..len]);
+
+				for (brd, n) in boards.iter().zip(&out[..len]) {
+					assert_eq!(*n as i32, BitBoard(*brd).one_aways_x(), "{backend:?}");
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn mask_tails() {
+		// Lengths that don't fill the last SIMD chunk
+		for backend in Backend::ALL.into_iter().filter(|b| b.is_supported()) {
+			for len in [23, 13, 5, 3] {
+				let masks = &ONE_AWAY_X[..len];
+
+				for brd in all_boards() {
+					let n = masks.iter().filter(|&&m| m & brd.0 == m).count() as u32;
+
+					assert_eq!(backend.count(brd.0, masks).unwrap_or(n), n, "{backend:?}");
+					assert_eq!(
+						backend.any(brd.0, masks).unwrap_or(n > 0),
+						n > 0,
+						"{backend:?}"
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn backends_agree() {
+		for backend in Backend::ALL.into_iter().filter(|b| b.is_supported()) {
+			for brd in all_boards() {
+				assert_eq!(
+					backend.one_aways_x(brd),
+					brd.one_aways_x(),
+					"{backend:?} {brd:?}"
+				);
+				assert_eq!(
+					backend.one_aways_o(brd),
+					brd.one_aways_o(),
+					"{backend:?} {brd:?}"
+				);
+				assert_eq!(backend.won_by_x(brd), brd.won_by_x(), "{backend:?} {brd:?}");
+				assert_eq!(backend.won_by_o(brd), brd.won_by_o(), "{backend:?} {brd:?}");
+				assert_eq!(
+					backend.possible_to_win(brd),
+					counting::possible_to_win(brd),
+					"{backend:?} {brd:?}"
+				);
+			}
+		}
+	}
+}
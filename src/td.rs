@@ -0,0 +1,203 @@
+//! TD(λ) learning of `EvalParams` from self-play
+//!
+//! The engine plays itself with a shallow search, the search score of every position is turned
+//! into a predicted result and the weights are moved so each prediction agrees more with the
+//! ones that follow it (and finally with the actual result). Everything runs on one thread from
+//! a fixed seed, so the same config always produces the same weights.
+
+use std::{io, path::PathBuf};
+
+use rand::{SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+
+use crate::{
+	board::{Slot, State},
+	counting::{score_game_with, search_with},
+	eval::EvalParams,
+	game::Game,
+	moves::generate_moves,
+	tuner::sigmoid,
+};
+
+/// Depth of the search used to pick moves and score positions during self-play
+const SEARCH_DEPTH: u8 = 4;
+
+#[derive(Debug, Clone)]
+pub struct TdConfig {
+	/// Number of self-play games
+	pub games: usize,
+	/// Learning rate
+	pub alpha: f64,
+	/// How much of each temporal difference flows back to earlier positions
+	pub lambda: f64,
+	/// Sigmoid scaling, see `tuner::sigmoid`
+	pub k: f64,
+	/// The first `random_plies` moves of every game are random so games don't repeat
+	pub random_plies: u8,
+	pub seed: u64,
+	/// Write the weights every `checkpoint_every` games (0 to never)
+	pub checkpoint_every: usize,
+	/// Checkpoints are written to `{checkpoint_prefix}-{game}.toml`
+	pub checkpoint_prefix: Option<PathBuf>,
+}
+
+impl Default for TdConfig {
+	fn default() -> Self {
+		TdConfig {
+			games: 1000,
+			alpha: 100.0,
+			lambda: 0.7,
+			k: 1.0,
+			random_plies: 4,
+			seed: 42,
+			checkpoint_every: 100,
+			checkpoint_prefix: None,
+		}
+	}
+}
+
+/// The weights the evaluation actually uses, divisors are kept at 1 or above
+fn round(weights: &[f64; EvalParams::NAMES.len()]) -> EvalParams {
+	let mut params = EvalParams::DEFAULT;
+
+	for (idx, w) in weights.iter().enumerate() {
+		let w = w.round() as i32;
+
+		*params.get_mut(idx) = if EvalParams::is_divisor(idx) {
+			w.max(1)
+		} else {
+			w
+		};
+	}
+
+	params
+}
+
+/// Finite difference gradient of the predicted result of `game` with respect to every weight
+fn gradient(
+	game: &Game,
+	turn: Slot,
+	params: &EvalParams,
+	k: f64,
+) -> [f64; EvalParams::NAMES.len()] {
+	let pred = |p: &EvalParams| sigmoid(score_game_with(game, turn, p), k);
+
+	std::array::from_fn(|idx| {
+		let mut up = *params;
+		*up.get_mut(idx) += 1;
+
+		let mut down = *params;
+		*down.get_mut(idx) -= 1;
+
+		if EvalParams::is_divisor(idx) && down.get(idx) < 1 {
+			pred(&up) - pred(params)
+		} else {
+			(pred(&up) - pred(&down)) / 2.0
+		}
+	})
+}
+
+/// One self-play game, returns every position (with the side that just moved) and the predicted
+/// result for X after each of them
+fn play(rng: &mut SmallRng, params: &EvalParams, cfg: &TdConfig) -> (Vec<(Game, Slot)>, Vec<f64>) {
+	let mut game = Game::new();
+	let mut positions = vec![];
+	let mut preds = vec![];
+
+	while game.state() == State::Undecided {
+		let lgms = generate_moves(&game);
+
+		if lgms.is_empty() {
+			break;
+		}
+
+		let (scr, mv) = search_with::<SEARCH_DEPTH>(&game, params);
+
+		if !positions.is_empty() {
+			preds.push(sigmoid(scr, cfg.k));
+		}
+
+		let mv = if positions.len() < cfg.random_plies as usize {
+			*lgms.choose(rng).unwrap()
+		} else {
+			mv.unwrap()
+		};
+
+		let side = game.to_move().slot();
+
+		game.make_move(mv).unwrap();
+		positions.push((game.clone(), side));
+	}
+
+	preds.push(match game.state() {
+		State::Won => 1.0,
+		State::Lost => 0.0,
+		_ => 0.5,
+	});
+
+	(positions, preds)
+}
+
+/// Runs `cfg.games` self-play games starting from `start` and returns the learned weights, or
+/// the error from writing a checkpoint
+pub fn train(start: EvalParams, cfg: &TdConfig) -> io::Result<EvalParams> {
+	let mut rng = SmallRng::seed_from_u64(cfg.seed);
+	let mut weights: [f64; EvalParams::NAMES.len()] =
+		std::array::from_fn(|idx| start.get(idx) as f64);
+
+	for game_idx in 1..=cfg.games {
+		let params = round(&weights);
+		let (positions, preds) = play(&mut rng, &params, cfg);
+
+		// `preds[t]` is the search's prediction for `positions[t]`, except for the last which is
+		// the actual result of the game
+		let diffs: Vec<f64> = preds.windows(2).map(|w| w[1] - w[0]).collect();
+		let mut step = [0.0; EvalParams::NAMES.len()];
+		let mut trace = 0.0;
+
+		for t in (0..diffs.len()).rev() {
+			trace = diffs[t] + cfg.lambda * trace;
+
+			let (game, turn) = &positions[t];
+			let grad = gradient(game, *turn, &params, cfg.k);
+
+			for idx in 0..EvalParams::NAMES.len() {
+				step[idx] += grad[idx] * trace;
+			}
+		}
+
+		for idx in 0..EvalParams::NAMES.len() {
+			weights[idx] += cfg.alpha * step[idx];
+		}
+
+		if let Some(prefix) = &cfg.checkpoint_prefix
+			&& cfg.checkpoint_every != 0
+			&& game_idx % cfg.checkpoint_every == 0
+		{
+			let path = format!("{}-{game_idx}.toml", prefix.display());
+
+			std::fs::write(&path, round(&weights).to_string())?;
+		}
+	}
+
+	Ok(round(&weights))
+}
+
+#[cfg(test)]
+mod test {
+	use super::{TdConfig, train};
+	use crate::eval::EvalParams;
+
+	#[test]
+	fn reproducible() {
+		let cfg = TdConfig {
+			games: 2,
+			checkpoint_every: 0,
+			..TdConfig::default()
+		};
+
+		let a = train(EvalParams::DEFAULT, &cfg).unwrap();
+		let b = train(EvalParams::DEFAULT, &cfg).unwrap();
+
+		assert_eq!(a, b);
+	}
+}
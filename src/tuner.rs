@@ -0,0 +1,268 @@
+//! Texel-style tuning of `EvalParams`
+//!
+//! Every position of every game in the dataset is labelled with the result of the game, the
+//! weights are then nudged one at a time until the static evaluation (squashed through a sigmoid)
+//! predicts those results as well as it can.
+
+use std::{sync::mpsc, thread};
+
+use crate::{
+	board::Slot, counting::score_game_with, eval::EvalParams, game::Game, moves::parse_move,
+};
+
+#[derive(Debug, Clone)]
+pub struct Sample {
+	pub game: Game,
+	/// The side that made the last move, as passed to `score_game`
+	pub turn: Slot,
+	/// 1.0 if X won the game, 0.0 if O won and 0.5 for a tie
+	pub result: f64,
+}
+
+/// Reads a dataset made of one game per line: the moves in `a5` notation followed by the result
+/// (`1-0` if X won, `0-1` if O won, `1/2-1/2` for a tie). X makes the first move, as in the CLI.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn load_dataset(src: &str) -> Result<Vec<Sample>, &'static str> {
+	let mut samples = vec![];
+
+	for line in src.lines() {
+		let line = line.trim();
+
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let mut toks: Vec<&str> = line.split_whitespace().collect();
+
+		let result = match toks.pop() {
+			Some("1-0") => 1.0,
+			Some("0-1") => 0.0,
+			Some("1/2-1/2") => 0.5,
+			_ => return Err("game must end with a result (1-0, 0-1 or 1/2-1/2)"),
+		};
+
+		let mut game = Game::new();
+
+		for tok in toks {
+			let mv = parse_move(&tok.to_ascii_lowercase(), game.active).map_err(|e| e.message())?;
+			let side = game.to_move().slot();
+			game.make_move(mv).map_err(|e| e.message())?;
+
+			samples.push(Sample {
+				game: game.clone(),
+				turn: side,
+				result,
+			});
+		}
+	}
+
+	Ok(samples)
+}
+
+/// Converts an evaluation into the expected result of the game for X
+pub fn sigmoid(score: i32, k: f64) -> f64 {
+	1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+/// Sum of the squared errors of `samples`
+fn sum_error(samples: &[Sample], params: &EvalParams, k: f64) -> f64 {
+	samples
+		.iter()
+		.map(|smp| {
+			let pred = sigmoid(score_game_with(&smp.game, smp.turn, params), k);
+
+			(smp.result - pred).powi(2)
+		})
+		.sum()
+}
+
+/// Splits `samples` once across a thread per core, which stay up for all of `f`. `f` gets a
+/// function giving the `error` of any weights and `k`, so searches that try many weights don't
+/// start new threads for each one
+fn with_error<R>(samples: &[Sample], f: impl FnOnce(&dyn Fn(&EvalParams, f64) -> f64) -> R) -> R {
+	if samples.is_empty() {
+		return f(&|_, _| 0.0);
+	}
+
+	let threads = thread::available_parallelism().map_or(1, |n| n.get());
+	let chunk = samples.len().div_ceil(threads);
+
+	thread::scope(|s| {
+		let (sums_tx, sums) = mpsc::channel();
+		let workers: Vec<_> = samples
+			.chunks(chunk)
+			.map(|chunk| {
+				let (tx, rx) = mpsc::channel::<(EvalParams, f64)>();
+				let sums_tx = sums_tx.clone();
+
+				s.spawn(move || {
+					for (params, k) in rx {
+						sums_tx.send(sum_error(chunk, &params, k)).unwrap();
+					}
+				});
+
+				tx
+			})
+			.collect();
+
+		let error = |params: &EvalParams, k: f64| {
+			for tx in &workers {
+				tx.send((*params, k)).unwrap();
+			}
+
+			sums.iter().take(workers.len()).sum::<f64>() / samples.len() as f64
+		};
+
+		// The workers stop once their senders are dropped at the end of this closure
+		f(&error)
+	})
+}
+
+/// Mean squared error between the predicted and actual results of `samples`
+pub fn error(samples: &[Sample], params: &EvalParams, k: f64) -> f64 {
+	with_error(samples, |error| error(params, k))
+}
+
+/// Finds the sigmoid scaling constant which best fits `params` to `samples`, this should be done
+/// once before tuning so the tuner doesn't just rescale every weight
+pub fn find_k(samples: &[Sample], params: &EvalParams) -> f64 {
+	with_error(samples, |error| {
+		let mut best = (1.0, error(params, 1.0));
+		let mut step = 0.5;
+
+		while step > 0.0005 {
+			for k in [best.0 - step, best.0 + step] {
+				if k <= 0.0 {
+					continue;
+				}
+
+				let err = error(params, k);
+				if err < best.1 {
+					best = (k, err);
+				}
+			}
+
+			step /= 2.0;
+		}
+
+		best.0
+	})
+}
+
+/// Local search over the weights, each pass tries moving every weight up and down by one and keeps
+/// any change that lowers the error. Stops when a pass improves nothing or after `max_passes`.
+pub fn tune(samples: &[Sample], start: EvalParams, k: f64, max_passes: usize) -> EvalParams {
+	with_error(samples, |error| {
+		let mut best = start;
+		let mut best_err = error(&best, k);
+
+		for _ in 0..max_passes {
+			let mut improved = false;
+
+			for idx in 0..EvalParams::NAMES.len() {
+				for delta in [1, -1] {
+					let mut cand = best;
+					*cand.get_mut(idx) += delta;
+
+					if EvalParams::is_divisor(idx) && cand.get(idx) < 1 {
+						continue;
+					}
+
+					let err = error(&cand, k);
+					if err < best_err {
+						best = cand;
+						best_err = err;
+						improved = true;
+
+						break;
+					}
+				}
+			}
+
+			if !improved {
+				break;
+			}
+		}
+
+		best
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use rand::{SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+
+	use super::{Sample, error, load_dataset, tune};
+	use crate::{
+		board::{Slot, State},
+		eval::EvalParams,
+		game::Game,
+		moves::generate_moves,
+	};
+
+	const DATA: &str = "
+		# comments and blank lines are skipped
+		e5 e1 a5 e9 i5 1/2-1/2
+		a1 a2 b1 a3 c1 1-0
+	";
+
+	#[test]
+	fn load() {
+		let samples = load_dataset(DATA).unwrap();
+
+		assert_eq!(samples.len(), 10);
+		assert_eq!(samples[0].turn, Slot::X);
+		assert_eq!(samples[1].turn, Slot::O);
+		assert_eq!(samples[9].result, 1.0);
+
+		assert!(load_dataset("a1 a2").is_err());
+		assert!(load_dataset("a1 a1 1-0").is_err());
+	}
+
+	/// Every position of `n` random games played to the end, labelled with their results
+	fn playouts(n: usize) -> Vec<Sample> {
+		let mut rng = SmallRng::seed_from_u64(7);
+		let mut samples = vec![];
+
+		for _ in 0..n {
+			let mut game = Game::new();
+			let mut positions = vec![];
+
+			while game.state() == State::Undecided {
+				let side = game.to_move().slot();
+				let mv = *generate_moves(&game).choose(&mut rng).unwrap();
+
+				game.make_move(mv).unwrap();
+				positions.push((game.clone(), side));
+			}
+
+			let result = match game.state() {
+				State::Won => 1.0,
+				State::Lost => 0.0,
+				_ => 0.5,
+			};
+
+			samples.extend(
+				positions
+					.into_iter()
+					.map(|(game, turn)| Sample { game, turn, result }),
+			);
+		}
+
+		samples
+	}
+
+	#[test]
+	fn tuning_fixes_bad_weights() {
+		let samples = playouts(40);
+
+		// The meta-board counts against whoever is ahead on it
+		let mut start = EvalParams::DEFAULT;
+		start.meta_weight = -100;
+
+		let tuned = tune(&samples, start, 1.0, 10);
+
+		assert!(tuned.meta_weight > start.meta_weight);
+		assert!(error(&samples, &tuned, 1.0) < error(&samples, &start, 1.0) - 0.005);
+	}
+}
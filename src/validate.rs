@@ -0,0 +1,260 @@
+//! Consistency checks for a `Game`, whose boards can be written to directly
+
+use std::fmt::Display;
+
+use crate::{
+	bitboard::consts::{E_MASK, O_MASK, ST_MASK, TURN_MASK, UN_MASK, X_MASK},
+	board::{Player, State},
+	game::{Game, decided_state},
+	lut,
+};
+
+/// Something wrong with a position, `Game::validate` lists every one it finds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationError {
+	/// A cell isn't exactly one of X, O and empty
+	Cells { board: u8 },
+	/// Bits are set outside the cells and the state, other than the game bits of the first board
+	StrayBits { board: u8 },
+	/// The state of a board doesn't follow from its cells
+	BoardState {
+		board: u8,
+		found: State,
+		expected: State,
+	},
+	/// Both sides have a line in the same board
+	BothLines { board: u8 },
+	/// The state of the game doesn't follow from the states of the boards
+	GameState { found: State, expected: State },
+	/// `active` is past 9
+	Active { active: u8 },
+	/// The active board has already been decided
+	ActiveDecided { active: u8 },
+	/// One side has more than one move more than the other
+	MoveCounts { x: u32, o: u32 },
+	/// The side to move has already made more moves than the other
+	ToMove { side: Player, x: u32, o: u32 },
+}
+
+impl ValidationError {
+	pub const fn message(&self) -> &'static str {
+		match self {
+			ValidationError::Cells { .. } => "cells must be exactly one of x, o and empty",
+			ValidationError::StrayBits { .. } => "bits set outside the cells and state",
+			ValidationError::BoardState { .. } => "board state doesn't match its cells",
+			ValidationError::BothLines { .. } => "both sides have a line in the same board",
+			ValidationError::GameState { .. } => "game state doesn't match the boards",
+			ValidationError::Active { .. } => "active board must be within 0 to 9",
+			ValidationError::ActiveDecided { .. } => "the active board has already been decided",
+			ValidationError::MoveCounts { .. } => "one side has played too many moves",
+			ValidationError::ToMove { .. } => "the side to move is already a move ahead",
+		}
+	}
+}
+
+impl Display for ValidationError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let letter = |board: u8| (board + b'a') as char;
+
+		match *self {
+			ValidationError::Cells { board }
+			| ValidationError::StrayBits { board }
+			| ValidationError::BothLines { board } => {
+				write!(f, "{} ({})", self.message(), letter(board))
+			}
+			ValidationError::BoardState {
+				board,
+				found,
+				expected,
+			} => write!(
+				f,
+				"{} ({}: {found:?}, should be {expected:?})",
+				self.message(),
+				letter(board)
+			),
+			ValidationError::GameState { found, expected } => {
+				write!(f, "{} ({found:?}, should be {expected:?})", self.message())
+			}
+			ValidationError::Active { active } => write!(f, "{} ({active})", self.message()),
+			ValidationError::ActiveDecided { active } => {
+				write!(f, "{} ({})", self.message(), letter(active))
+			}
+			ValidationError::MoveCounts { x, o } => {
+				write!(f, "{} ({x} for x, {o} for o)", self.message())
+			}
+			ValidationError::ToMove { side, x, o } => {
+				write!(
+					f,
+					"{} ({side} to move, {x} for x, {o} for o)",
+					self.message()
+				)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Game {
+	/// Checks the position is one that could come up in a game. X moves first, so normally O is
+	/// to move when X is a move ahead and X when they're level. A skipped turn or `flip` swaps
+	/// that around, so all that's checked is that the counts are within one of each other and
+	/// the side to move isn't the one ahead
+	pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+		let mut errs = vec![];
+		let (mut xs, mut os) = (0, 0);
+
+		for (bdx, brd) in self.boards.iter().enumerate() {
+			let board = bdx as u8;
+			let (x, o, e) = (
+				brd.0 & X_MASK,
+				(brd.0 & O_MASK) >> 9,
+				(brd.0 & E_MASK) >> 18,
+			);
+
+			if x | o | e != 0x1FF || (x & o) | (x & e) | (o & e) != 0 {
+				errs.push(ValidationError::Cells { board });
+			}
+
+			let game_bits = if bdx == 0 { UN_MASK | TURN_MASK } else { 0 };
+			let allowed = X_MASK | O_MASK | E_MASK | ST_MASK | game_bits;
+			if brd.0 & !allowed != 0 {
+				errs.push(ValidationError::StrayBits { board });
+			}
+
+			let expected = decided_state(*brd);
+			if brd.state() != expected {
+				errs.push(ValidationError::BoardState {
+					board,
+					found: brd.state(),
+					expected,
+				});
+			}
+
+			if lut::won_by_x(*brd) && lut::won_by_o(*brd) {
+				errs.push(ValidationError::BothLines { board });
+			}
+
+			xs += x.count_ones();
+			os += o.count_ones();
+		}
+
+		let expected = decided_state(self.shrink());
+		if self.state() != expected {
+			errs.push(ValidationError::GameState {
+				found: self.state(),
+				expected,
+			});
+		}
+
+		if self.active > 9 {
+			errs.push(ValidationError::Active {
+				active: self.active,
+			});
+		} else if self.active != 9 && self.boards[self.active as usize].state() != State::Undecided
+		{
+			errs.push(ValidationError::ActiveDecided {
+				active: self.active,
+			});
+		}
+
+		let side = self.to_move();
+		if xs.abs_diff(os) > 1 {
+			errs.push(ValidationError::MoveCounts { x: xs, o: os });
+		} else if (side == Player::X && xs > os) || (side == Player::O && os > xs) {
+			errs.push(ValidationError::ToMove { side, x: xs, o: os });
+		}
+
+		if errs.is_empty() { Ok(()) } else { Err(errs) }
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::ValidationError;
+	use crate::{
+		board::{Player, State},
+		game::Game,
+		moves::Move,
+	};
+
+	#[test]
+	fn valid_games() {
+		assert_eq!(Game::new().validate(), Ok(()));
+
+		for times in 0..70 {
+			let game = Game::random(times);
+
+			assert_eq!(game.validate(), Ok(()));
+			assert_eq!(game.flip().validate(), Ok(()));
+		}
+
+		let mut skipped = Game::new();
+		skipped.set_to_move(Player::X);
+		assert_eq!(skipped.validate(), Ok(()));
+	}
+
+	#[test]
+	fn lists_every_error() {
+		let mut game = Game::new().sim_move(Move::new(4, 4)).unwrap();
+
+		// X and O in the same cell, and an O line with no state
+		game.boards[4].0 |= 1 << 13;
+		game.boards[2].0 = (game.boards[2].0 & !(0b111 << 18)) | (0b111 << 9);
+		game.boards[1].0 |= 1 << 30;
+		game.set_state(State::Tied);
+
+		assert_eq!(
+			game.validate(),
+			Err(vec![
+				ValidationError::StrayBits { board: 1 },
+				ValidationError::BoardState {
+					board: 2,
+					found: State::Undecided,
+					expected: State::Lost,
+				},
+				ValidationError::Cells { board: 4 },
+				ValidationError::GameState {
+					found: State::Tied,
+					expected: State::Undecided,
+				},
+				ValidationError::MoveCounts { x: 1, o: 4 },
+			])
+		);
+
+		// X is a move ahead but it's X's turn again
+		let mut game = Game::new().sim_move(Move::new(4, 4)).unwrap();
+		game.set_to_move(Player::X);
+		assert_eq!(
+			game.validate(),
+			Err(vec![ValidationError::ToMove {
+				side: Player::X,
+				x: 1,
+				o: 0,
+			}])
+		);
+
+		let mut game = Game::new();
+		game.active = 10;
+		assert_eq!(
+			game.validate(),
+			Err(vec![ValidationError::Active { active: 10 }])
+		);
+
+		let mut game =
+			Game::from_moves([Move::new(4, 0), Move::new(0, 4), Move::new(4, 1)]).unwrap();
+		game.boards[0].set_state(State::Tied);
+		game.active = 0;
+		assert_eq!(
+			game.validate(),
+			Err(vec![
+				ValidationError::BoardState {
+					board: 0,
+					found: State::Tied,
+					expected: State::Undecided,
+				},
+				ValidationError::ActiveDecided { active: 0 },
+			])
+		);
+	}
+}